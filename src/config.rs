@@ -1,18 +1,24 @@
 use std::borrow::Cow;
+use std::ffi::OsString;
 use std::fmt::{Display, Write};
 use std::io::{ErrorKind, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use std::{env, fmt, fs, io, panic, process};
 
-use ahash::HashSet;
+use ahash::{HashMap, HashSet};
 use anyhow::{anyhow, Context};
-use clap::{command, crate_description, Arg, ArgMatches};
+use clap::{command, crate_description, Arg, ArgAction, ArgMatches, ColorChoice, Command};
 use directories::{BaseDirs, ProjectDirs};
+use is_executable::IsExecutable;
 use is_terminal::IsTerminal;
 use toml::{map::Map, Value};
 
 use crate::imstr::ImStr;
-use crate::style::{bold, style_stderr, style_stdout};
+use crate::schedule::Schedule;
+use crate::style::{self, bold, style_stderr, style_stdout};
+use crate::tag;
+use crate::template;
 
 const SHORT_EXAMPLE: &str = r#"    # A short example config; see `--help` for more info.
     [menu]
@@ -36,40 +42,524 @@ pub fn get() -> anyhow::Result<Config> {
     let base_dirs = BaseDirs::new().expect("unreachable");
     let args = parse_args(&dirs);
 
-    let config = if let Some(path) = args.get_one::<String>("PATTERN") {
-        fs::read_to_string(path).context(format!(
-            "unable to read config file `{}`",
-            style_stderr!(bold(), "{path}")
-        ))?
+    // With a pattern, a piped config is merged into it; without either, the home config is used.
+    // A config read from `--fd` takes the place of stdin, in `load`.
+    // With `--stdin-entries`, stdin is read even from a terminal, since it's the whole menu.
+    let read_stdin = args.get_flag("stdin-entries") || !io::stdin().is_terminal();
+    let piped = if args.contains_id("fd") || !read_stdin {
+        None
     } else {
         let mut buf = String::new();
         io::stdin()
             .read_to_string(&mut buf)
             .context("unable to read piped input")?;
-        buf
+        Some(buf)
     };
-    let config = config
-        .parse::<Value>()
-        .context("found incorrect formatting in target config")?;
 
-    let home_config = read_home_config(dirs.config_dir())?;
-    let home_config = home_config.map(|config| {
-        config.parse::<Value>().context(format!(
-            "found incorrect formatting in home config `{}`",
-            style_stderr!(
-                bold(),
-                "{}",
-                dirs.config_dir().join("config.toml").display()
-            )
-        ))
-    });
-    let home_config = if let Some(home_config) = home_config {
-        Some(home_config?)
+    load(args, dirs, base_dirs, piped)
+}
+
+/// Load a config from `args` as if they were given on the command line,
+/// with `piped` standing in for a config piped to stdin.
+///
+/// Unlike [`get`], this never reads stdin or exits the process,
+/// so it can be used to embed dmm in another program.
+///
+/// ```no_run
+/// let config = dmm::config::get_from(["dmm", "menu.toml"], None).unwrap();
+/// let exit_code = dmm::menu::select_and_execute(&config).unwrap().unwrap_or(0);
+/// ```
+pub fn get_from<I, T>(args: I, piped: Option<String>) -> anyhow::Result<Config>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let dirs = ProjectDirs::from("", "", "dmm")
+        .context("no valid home directory could be detected")
+        .context("could not access config or cache directories")?;
+    let base_dirs = BaseDirs::new().context("no valid home directory could be detected")?;
+    let args = command(&dirs).try_get_matches_from(args)?;
+
+    load(args, dirs, base_dirs, piped)
+}
+
+/// Load the config from the pattern, or `piped` if there is no pattern file, and the home config.
+///
+/// With `--fd` and no `piped`, the config is read from that file descriptor instead;
+/// it can only be read once, so reloading passes what was read as `piped`.
+fn load(
+    args: ArgMatches,
+    dirs: ProjectDirs,
+    base_dirs: BaseDirs,
+    piped: Option<String>,
+) -> anyhow::Result<Config> {
+    let piped = match (args.get_one::<u32>("fd"), piped) {
+        (Some(&fd), None) => Some(read_fd(fd).context(format!(
+            "unable to read config from file descriptor `{}`",
+            style_stderr!(bold(), "{fd}")
+        ))?),
+        (_, piped) => piped,
+    };
+    // Describes the piped config in errors, as if it were a file.
+    let piped_name = match args.get_one::<u32>("fd") {
+        Some(fd) => format!("<fd {fd}>"),
+        None => String::from("<stdin>"),
+    };
+    let patterns = args
+        .get_many::<String>("PATTERN")
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<&str>>();
+    let format = args.get_one::<String>("format").map(String::as_str);
+    let home_config_path = dirs.config_dir().join("config.toml");
+    let stdin_entries = args.get_flag("stdin-entries");
+    let home_config_only = patterns.is_empty() && piped.is_none() && !stdin_entries;
+    let mut layers = Vec::new();
+
+    if stdin_entries {
+        // Each line is an entry running the same command, so nothing is parsed as a config.
+        let run = Value::String(args.get_one::<String>("run").expect("required").clone());
+        let menu = piped
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| (line.to_owned(), run.clone()))
+            .collect();
+        let entries = Layer {
+            value: table([("menu", Value::Table(menu))]),
+            base: Base::Dir(PathBuf::new()),
+            name: Some(String::from("piped entries")),
+        };
+        push_layer(&mut layers, entries, None, &mut Vec::new())?;
+    } else if let Some(config) = piped.as_deref().filter(|_| patterns.is_empty()) {
+        let format = match format {
+            Some("json") => Format::Json,
+            _ => Format::Toml,
+        };
+        let pattern = Layer {
+            value: format.parse(config).with_context(|| {
+                format!(
+                    "found incorrect formatting in `{}`",
+                    style_stderr!(bold(), "{piped_name}")
+                )
+            })?,
+            base: Base::Dir(PathBuf::new()),
+            name: None,
+        };
+        push_layer(&mut layers, pattern, None, &mut Vec::new())?;
     } else {
-        None
+        // Later patterns take precedence, so they're added first.
+        for pattern in patterns.iter().rev() {
+            let name = (patterns.len() > 1)
+                .then(|| format!("pattern `{}`", style_stderr!(bold(), "{pattern}")));
+            push_pattern(&mut layers, pattern, name, format)?;
+        }
+    }
+
+    // Piped along with a pattern, it adds to the pattern and its includes, which take precedence.
+    let merged = piped
+        .as_deref()
+        .filter(|piped| !patterns.is_empty() && !stdin_entries && !piped.trim().is_empty());
+    if let Some(merged) = merged {
+        let format = match format {
+            Some("json") => Format::Json,
+            _ => Format::Toml,
+        };
+        let merged = Layer {
+            value: format.parse(merged).with_context(|| {
+                format!(
+                    "found incorrect formatting in `{}`",
+                    style_stderr!(bold(), "{piped_name}")
+                )
+            })?,
+            base: Base::Dir(PathBuf::new()),
+            name: Some(String::from("piped config")),
+        };
+        push_layer(&mut layers, merged, None, &mut Vec::new())?;
+    }
+
+    if let Some(home_config) = read_home_config(dirs.config_dir())? {
+        let home_config = Format::Toml.parse(&home_config).context(format!(
+            "found incorrect formatting in home config `{}`",
+            style_stderr!(bold(), "{}", home_config_path.display())
+        ))?;
+        let home_config = Layer {
+            value: home_config,
+            base: Base::Dir(dirs.config_dir().to_path_buf()),
+            name: Some(format!(
+                "home config `{}`",
+                style_stderr!(bold(), "{}", home_config_path.display())
+            )),
+        };
+        push_layer(
+            &mut layers,
+            home_config,
+            Some(&home_config_path),
+            &mut Vec::new(),
+        )?;
+    } else if home_config_only {
+        return Err(anyhow!(
+            "no pattern was given, and none was found at `{}`",
+            style_stderr!(bold(), "{}", home_config_path.display())
+        )
+        .context("a pattern must be specified, piped in, or written to the home config"));
+    }
+
+    let mut config = Config::try_new(&layers, args, dirs, base_dirs)?;
+    config.piped = piped;
+
+    Ok(config)
+}
+
+/// Add the pattern at `pattern`, which is a file, a directory of configs, or an http url.
+///
+/// `name` describes the pattern, if there's more than one; `format` is the `--format` given.
+fn push_pattern(
+    layers: &mut Vec<Layer>,
+    pattern: &str,
+    name: Option<String>,
+    format: Option<&str>,
+) -> anyhow::Result<()> {
+    let path = Path::new(pattern);
+    let format = match format {
+        Some("json") => Format::Json,
+        Some(_) => Format::Toml,
+        None => Format::from_path(path),
+    };
+
+    if pattern.starts_with("http://") || pattern.starts_with("https://") {
+        let config = fetch(pattern).context(format!(
+            "unable to fetch config from `{}`",
+            style_stderr!(bold(), "{pattern}")
+        ))?;
+        let pattern = Layer {
+            value: format.parse(&config).context(format!(
+                "found incorrect formatting in `{}`",
+                style_stderr!(bold(), "{pattern}")
+            ))?,
+            base: Base::Url(pattern.to_owned()),
+            name,
+        };
+        return push_layer(layers, pattern, Some(path), &mut Vec::new());
+    }
+
+    if path.is_dir() {
+        // A directory is a pattern that includes each of its configs, in order of file name.
+        let files = toml_files(path)?;
+        let pattern = Layer {
+            value: table([(
+                "include",
+                Value::Array(files.into_iter().map(Value::String).collect()),
+            )]),
+            base: Base::Dir(path.to_path_buf()),
+            name,
+        };
+        return push_layer(layers, pattern, Some(path), &mut Vec::new());
+    }
+
+    let config = fs::read_to_string(path).context(format!(
+        "unable to read config file `{}`",
+        style_stderr!(bold(), "{}", path.display())
+    ))?;
+    let pattern = Layer {
+        value: format.parse(&config).context(format!(
+            "found incorrect formatting in `{}`",
+            style_stderr!(bold(), "{}", path.display())
+        ))?,
+        base: Base::Dir(path.parent().unwrap_or_else(|| Path::new("")).to_path_buf()),
+        name,
+    };
+    push_layer(layers, pattern, Some(path), &mut Vec::new())
+}
+
+/// The names of the `.toml` files in `dir`, sorted; it's an error if there are none.
+fn toml_files(dir: &Path) -> anyhow::Result<Vec<String>> {
+    let read_error = || {
+        format!(
+            "unable to read config directory `{}`",
+            style_stderr!(bold(), "{}", dir.display())
+        )
+    };
+
+    let mut files = Vec::new();
+    for file in fs::read_dir(dir).with_context(read_error)? {
+        let path = file.with_context(read_error)?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+            && path.is_file()
+        {
+            let name = path
+                .file_name()
+                .expect("unreachable")
+                .to_str()
+                .with_context(|| {
+                    format!(
+                        "the config file name `{}` contains invalid unicode",
+                        style_stderr!(bold(), "{}", path.display())
+                    )
+                })?;
+            files.push(name.to_owned());
+        }
+    }
+
+    if files.is_empty() {
+        return Err(anyhow!(
+            "the config directory `{}` has no `.toml` files",
+            style_stderr!(bold(), "{}", dir.display())
+        ));
+    }
+    files.sort_unstable();
+
+    Ok(files)
+}
+
+/// The format of a config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    /// Get the format from the extension of `path`; anything other than `.json` is toml.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+
+    /// Parse `config`; a toml error shows the line it's on, after toml's own message.
+    ///
+    /// json is parsed into the same [`Value`] toml would be, so it must be an object,
+    /// and `null`, which toml has no equivalent for, is an error.
+    ///
+    /// ```
+    /// use dmm::config::Format;
+    ///
+    /// let json = r#"{ "menu": { "htop": { "run": ["htop"], "group": 1 } } }"#;
+    /// let toml = r#"menu.htop = { run = ["htop"], group = 1 }"#;
+    /// assert_eq!(Format::Json.parse(json).unwrap(), Format::Toml.parse(toml).unwrap());
+    ///
+    /// assert!(Format::Json.parse(r#"{ "menu": null }"#).is_err());
+    /// assert!(Format::Json.parse(r#"{ "menu": {} } trailing"#).is_err());
+    /// assert!(Format::Json.parse(r#"{ "group": 01 }"#).is_err());
+    /// assert!(Format::Json.parse(r#"{ "group": 18446744073709551615 }"#).is_err());
+    /// assert!(Format::Json.parse("[1, 2]").is_err());
+    /// ```
+    pub fn parse(self, config: &str) -> anyhow::Result<Value> {
+        match self {
+            Self::Toml => config.parse::<Value>().map_err(|err| {
+                let line = err
+                    .line_col()
+                    .and_then(|(line, _)| Some((line + 1, config.lines().nth(line)?)));
+                match line {
+                    Some((number, line)) => anyhow!("{err}").context(format!(
+                        "line {number} is `{}`",
+                        style_stderr!(bold(), "{}", line.trim())
+                    )),
+                    None => anyhow!("{err}"),
+                }
+            }),
+            Self::Json => match serde_json::from_str::<Value>(config)? {
+                value @ Value::Table(_) => Ok(value),
+                _ => Err(anyhow!("a json config must be an object")),
+            },
+        }
+    }
+}
+
+/// A parsed config file.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub value: Value,
+    /// What relative paths in the config are relative to.
+    pub base: Base,
+    /// Describes where the config came from, or `None` for the pattern.
+    pub name: Option<String>,
+}
+
+/// What relative paths in a config are relative to.
+#[derive(Debug, Clone)]
+pub enum Base {
+    /// The directory of a config read from a file, or the current one for piped input.
+    Dir(PathBuf),
+    /// The url of a downloaded config, which its includes are downloaded relative to;
+    /// any other path in it must be absolute, since it has no directory.
+    Url(String),
+}
+
+impl Layer {
+    fn error(&self) -> String {
+        match &self.name {
+            Some(name) => format!("found a problem with {name}"),
+            None => target_config_error().to_owned(),
+        }
+    }
+}
+
+/// Add a config to `layers`, followed by each config it includes, in order.
+///
+/// `path` is the path to the config, if it was read from a file,
+/// and `including` holds the canonical paths of the configs that included it.
+fn push_layer(
+    layers: &mut Vec<Layer>,
+    layer: Layer,
+    path: Option<&Path>,
+    including: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let includes = layer
+        .value
+        .get("include")
+        .map(try_into_array("include"))
+        .transpose()
+        .and_then(|includes| {
+            includes
+                .into_iter()
+                .flatten()
+                .map(try_into_array_string("include"))
+                .collect::<anyhow::Result<Vec<ImStr>>>()
+        })
+        .context(layer.error())?;
+    let base = layer.base.clone();
+    let includer = match path {
+        Some(path) => style_stderr!(bold(), "{}", path.display()),
+        None => String::from("piped input"),
     };
 
-    Config::try_new(&config, home_config.as_ref(), args, dirs, base_dirs)
+    if let Some(path) = path {
+        including.push(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+    }
+    layers.push(layer);
+
+    for include in includes {
+        let read_error = |path: &Path| {
+            format!(
+                "unable to read config file `{}` included by `{includer}`",
+                style_stderr!(bold(), "{}", path.display())
+            )
+        };
+        // A downloaded config only includes other urls, so it never reads local files.
+        let (path, canonical) = match &base {
+            Base::Dir(dir) => {
+                let path = dir.join(include.as_str());
+                let canonical = fs::canonicalize(&path).with_context(|| read_error(&path))?;
+                (path, canonical)
+            }
+            Base::Url(url) => {
+                let path = PathBuf::from(
+                    join_url(url, &include)
+                        .with_context(|| read_error(Path::new(include.as_str())))?,
+                );
+                (path.clone(), path)
+            }
+        };
+        if including.contains(&canonical) {
+            return Err(anyhow!(
+                "including config file `{}` from `{includer}` would create a cycle",
+                style_stderr!(bold(), "{}", path.display())
+            ));
+        }
+
+        let (config, included_base) = match &base {
+            Base::Dir(_) => (
+                fs::read_to_string(&path).with_context(|| read_error(&path))?,
+                Base::Dir(path.parent().expect("unreachable").to_path_buf()),
+            ),
+            Base::Url(_) => {
+                let url = path.to_string_lossy().into_owned();
+                (
+                    fetch(&url).with_context(|| read_error(&path))?,
+                    Base::Url(url),
+                )
+            }
+        };
+        let config = Format::from_path(&path).parse(&config).context(format!(
+            "found incorrect formatting in included config `{}`",
+            style_stderr!(bold(), "{}", path.display())
+        ))?;
+        let included = Layer {
+            value: config,
+            base: included_base,
+            name: Some(format!(
+                "included config `{}`",
+                style_stderr!(bold(), "{}", path.display())
+            )),
+        };
+        push_layer(layers, included, Some(&path), including)?;
+    }
+
+    if path.is_some() {
+        including.pop();
+    }
+
+    Ok(())
+}
+
+/// Download a pattern, following redirects; any response but a success is an error.
+#[cfg(feature = "url")]
+fn fetch(url: &str) -> anyhow::Result<String> {
+    let response = ureq::get(url).call().map_err(|err| match err {
+        ureq::Error::Status(status, response) => anyhow!(
+            "the server responded with `{}`",
+            style_stderr!(bold(), "{status} {}", response.status_text())
+        ),
+        ureq::Error::Transport(err) => anyhow!(err).context("the download failed"),
+    })?;
+
+    response
+        .into_string()
+        .context("unable to read the downloaded config, which must be valid unicode")
+}
+
+#[cfg(not(feature = "url"))]
+fn fetch(_: &str) -> anyhow::Result<String> {
+    Err(anyhow!(
+        "dmm was built without the `{}` feature, which is needed to read a pattern from a url",
+        style_stderr!(bold(), "url")
+    ))
+}
+
+/// Resolve `include` against the url of the config that includes it, like a link in a web page.
+#[cfg(feature = "url")]
+fn join_url(base: &str, include: &str) -> anyhow::Result<String> {
+    let url = url::Url::parse(base)?.join(include)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow!(
+            "a config downloaded from a url can only include http and https urls, not `{}`",
+            style_stderr!(bold(), "{url}")
+        ));
+    }
+
+    Ok(url.into())
+}
+
+/// Without the `url` feature, every url fails to download, so the include is left to fail the same way.
+#[cfg(not(feature = "url"))]
+fn join_url(_: &str, include: &str) -> anyhow::Result<String> {
+    Ok(include.to_owned())
+}
+
+/// Read the rest of an already open file descriptor, like one from process substitution.
+///
+/// It's opened again through `/dev/fd`, so the descriptor itself is left open for its owner.
+#[cfg(unix)]
+fn read_fd(fd: u32) -> anyhow::Result<String> {
+    fs::read_to_string(format!("/dev/fd/{fd}")).map_err(|err| match err.kind() {
+        ErrorKind::NotFound => anyhow!("the file descriptor isn't open"),
+        _ => anyhow!(err),
+    })
+}
+
+#[cfg(not(unix))]
+fn read_fd(_: u32) -> anyhow::Result<String> {
+    Err(anyhow!(
+        "`{}` is only supported on unix",
+        style_stderr!(bold(), "--fd")
+    ))
 }
 
 fn read_home_config(dirs: &Path) -> anyhow::Result<Option<String>> {
@@ -91,15 +581,41 @@ fn read_home_config(dirs: &Path) -> anyhow::Result<Option<String>> {
 }
 
 fn parse_args(dirs: &ProjectDirs) -> ArgMatches {
-    let args = command!()
+    // Checked before parsing, so the help and any errors from parsing aren't colored either.
+    if env::args_os()
+        .take_while(|arg| arg != "--")
+        .any(|arg| arg == "--no-color")
+    {
+        style::disable_color();
+    }
+
+    let args = command(dirs).get_matches();
+
+    if args.contains_id("home-config") {
+        println!("{}", dirs.config_dir().display());
+        process::exit(0);
+    }
+
+    args
+}
+
+fn command(dirs: &ProjectDirs) -> Command {
+    command!()
+        .color(if style::color_allowed() {
+            ColorChoice::Auto
+        } else {
+            ColorChoice::Never
+        })
         .about(concat!(crate_description!(), ".\n"))
         .long_about(format!(
             concat!(
                 crate_description!(),
                 ".\n",
                 "The toml config may be piped in instead of specifying a file path.\n",
+                "If both are given, the piped config adds entries and items the file doesn't have.\n",
                 "A config may be written at `{}/config.toml`.\n",
-                "This will define default options that are overridden by the main pattern."
+                "This will define default options that are overridden by the main pattern.\n",
+                "If there is no pattern, and stdin is a terminal, that config is used on its own."
             ),
             dirs.config_dir().display()
         ))
@@ -108,16 +624,187 @@ fn parse_args(dirs: &ProjectDirs) -> ArgMatches {
                 .help("Output the directory that will be checked for config files")
                 .long("home-config-path"),
         )
+        .arg(
+            Arg::new("dry-run")
+                .help("Print the selected commands instead of running them")
+                .long_help(
+                    "Print the selected commands instead of running them.\n\
+                     The menu is still shown, and placeholders are still substituted.",
+                )
+                .long("dry-run")
+                .short('n')
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("print")
+                .help("Print the selected commands instead of running them, for use in scripts")
+                .long_help(
+                    "Print each selected command on its own line instead of running it.\n\
+                     Unlike `--dry-run`, nothing else is printed, and history is still recorded.",
+                )
+                .long("print")
+                .short('p')
+                .conflicts_with("dry-run")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("output")
+                .help("Print each selection in a machine-readable format instead of running it")
+                .long_help(
+                    "Print each selection in a machine-readable format instead of running it.\n\
+                     With `json`, each is a line like\n\
+                     `{\"tag\": \"3\", \"index\": 3, \"name\": \"htop\", \"command\": \"htop\"}`;\n\
+                     `tag` is null unless tags are shown with `config.numbered`,\n\
+                     and `tag` and `index` are null for ad-hoc commands.",
+                )
+                .long("output")
+                .value_name("FORMAT")
+                .value_parser(["json"])
+                .conflicts_with_all(["dry-run", "print"]),
+        )
+        .arg(
+            Arg::new("format")
+                .help("The format of the pattern")
+                .long_help(
+                    "The format of the pattern.\n\
+                     By default, a pattern with a `.json` extension is json, and any other is toml.",
+                )
+                .long("format")
+                .value_parser(["toml", "json"]),
+        )
+        .arg(
+            Arg::new("stdin-entries")
+                .help("Show each line of stdin as an entry, running the command from `--run`")
+                .long_help(
+                    "Show each line of stdin as an entry, instead of reading a config from it.\n\
+                     Every entry runs the command from `--run`, with `{name}` replaced by its line,\n\
+                     like `ls | dmm --stdin-entries --run 'xdg-open {name}'`.\n\
+                     The home config is still used for config items.",
+                )
+                .long("stdin-entries")
+                .requires("run")
+                .conflicts_with_all(["PATTERN", "fd"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("run")
+                .help("The command each entry from `--stdin-entries` runs")
+                .long_help(
+                    "The command each entry from `--stdin-entries` runs, as a shell command.\n\
+                     `{name}` is replaced by the entry's line; see `config.source.run`.",
+                )
+                .long("run")
+                .value_name("COMMAND")
+                .requires("stdin-entries"),
+        )
+        .arg(
+            Arg::new("fd")
+                .help("Read the config from an open file descriptor instead of stdin")
+                .long_help(
+                    "Read the config from an open file descriptor instead of stdin,\n\
+                     like `--fd 3 3< menu.toml`; it's used like a piped config.\n\
+                     Only supported on unix.",
+                )
+                .long("fd")
+                .value_name("FD")
+                .value_parser(clap::value_parser!(u32)),
+        )
+        .arg(
+            Arg::new("menu")
+                .help("The name of the menu in `menus` to show")
+                .long_help(
+                    "The name of the menu in `menus` to show, instead of `menu`.\n\
+                     By default, the menu named `default` is shown, or the only one if there's one.",
+                )
+                .long("menu")
+                .short('m')
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("watch")
+                .help("Show the menu again after each selection, until nothing is selected")
+                .long_help(
+                    "Show the menu again after each selection, until nothing is selected.\n\
+                     The config is read again each time, so changes to it are picked up.\n\
+                     If the config can't be read, the error is shown, and the last config is used.",
+                )
+                .long("watch")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("check")
+                .help("Check the pattern for problems without showing the menu")
+                .long_help(
+                    "Check the pattern and home config for problems without showing the menu.\n\
+                     Exits with 0 if there are no problems, or 1 after listing them.",
+                )
+                .long("check")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dump-config")
+                .help("Print the resolved config as toml without showing the menu")
+                .long_help(
+                    "Print the resolved config as toml without showing the menu.\n\
+                     Includes are merged, defaults are filled in, and entries from\n\
+                     `config.path` and `config.source` are added to `menu`.\n\
+                     Hidden entries are kept, and `when` and `schedule` aren't checked.",
+                )
+                .long("dump-config")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("verbose")
+                .help("Log what's sent to and read from the launcher, and each command run")
+                .long_help(
+                    "Log what's sent to and read from the launcher, and each command run.\n\
+                     Once logs the launcher's arguments and output, and the commands run;\n\
+                     twice also logs each line of the menu and how long the launcher ran.",
+                )
+                .long("verbose")
+                .short('v')
+                .action(ArgAction::Count),
+        )
+        .arg(
+            Arg::new("no-color")
+                .help("Don't color output")
+                .long_help(
+                    "Don't color output.\n\
+                     Color is also disabled if the `NO_COLOR` environment variable is set,\n\
+                     or if the output isn't a terminal.",
+                )
+                .long("no-color")
+                .action(ArgAction::SetTrue),
+        )
         .arg({
             Arg::new("PATTERN")
-                .help("Path to a pattern file")
+                .help("Paths to pattern files")
                 .long_help(
-                    "Path to a pattern file.\n\
-                     Either this must be specified, or the pattern must be piped in.\n\
-                     If specified, anything piped through stdin is ignored.",
+                    "Paths to pattern files.\n\
+                     With more than one, their entries are merged, and later patterns take precedence.\n\
+                     If it's a directory, every `.toml` file in it is read, in order of file name;\n\
+                     earlier files take precedence, like `include`.\n\
+                     If not specified, the pattern is read from stdin;\n\
+                     if stdin is a terminal, only the home config is used.\n\
+                     If specified, a config piped through stdin is merged into it;\n\
+                     entries and config items in the pattern take precedence over piped ones.\n\
+                     May be an http or https url if dmm was built with the `url` feature.",
                 )
                 .index(1)
+                .num_args(1..)
         })
+        .arg(
+            Arg::new("LAUNCHER_ARGS")
+                .help("Extra arguments to pass to the launcher, after `--`")
+                .long_help(
+                    "Extra arguments to pass to the launcher, after `--`.\n\
+                     These are passed after any arguments from the config, so they take precedence.",
+                )
+                .index(2)
+                .num_args(1..)
+                .allow_hyphen_values(true)
+                .last(true),
+        )
         .after_help(format!(
             "{}\n{}",
             style_stdout!(bold().set_underline(true), "Example Pattern:"),
@@ -127,33 +814,89 @@ fn parse_args(dirs: &ProjectDirs) -> ArgMatches {
             "{}\n{}",
             style_stdout!(bold().set_underline(true), "Example Pattern:"),
             LONG_EXAMPLE
-        ));
-    let args = if io::stdin().is_terminal() {
-        args.arg_required_else_help(true)
-    } else {
-        args
-    };
-
-    let args = args.get_matches();
-
-    if args.contains_id("home-config") {
-        println!("{}", dirs.config_dir().display());
-        process::exit(0);
-    }
-
-    args
+        ))
 }
 
 #[derive(Debug, Clone)]
 pub enum Run {
     Shell(ImStr),
     Bare(Vec<ImStr>),
+    /// Commands run one after another, stopping at the first that fails unless `ignore_errors`.
+    Steps {
+        steps: Vec<Run>,
+        ignore_errors: bool,
+    },
 }
 
 impl Run {
     pub fn binary(run: ImStr) -> Self {
         Self::Bare(vec![run])
     }
+
+    /// Substitute placeholders in the command and each of its arguments.
+    pub fn render<F>(&self, mut resolve: F) -> anyhow::Result<Self>
+    where
+        F: FnMut(&str) -> anyhow::Result<ImStr>,
+    {
+        self.render_with(&mut resolve)
+    }
+
+    // Not generic, since rendering each of `Self::Steps` would otherwise recurse without end.
+    fn render_with(
+        &self,
+        resolve: &mut dyn FnMut(&str) -> anyhow::Result<ImStr>,
+    ) -> anyhow::Result<Self> {
+        match self {
+            Self::Shell(command) => Ok(Self::Shell(template::render(command, resolve)?.into())),
+            Self::Bare(command) => Ok(Self::Bare(
+                command
+                    .iter()
+                    .map(|arg| template::render(arg, &mut *resolve).map(ImStr::from))
+                    .collect::<anyhow::Result<Vec<ImStr>>>()?,
+            )),
+            Self::Steps {
+                steps,
+                ignore_errors,
+            } => Ok(Self::Steps {
+                steps: steps
+                    .iter()
+                    .map(|step| step.render_with(resolve))
+                    .collect::<anyhow::Result<Vec<Self>>>()?,
+                ignore_errors: *ignore_errors,
+            }),
+        }
+    }
+}
+
+impl Run {
+    /// Whether there's no command to run, like an empty or whitespace only string,
+    /// or steps that have any such command.
+    ///
+    /// ```
+    /// use dmm::config::Run;
+    ///
+    /// assert!(Run::Shell(" \t".into()).is_empty());
+    /// assert!(Run::Bare(vec![]).is_empty());
+    /// assert!(!Run::Bare(vec!["true".into(), "".into()]).is_empty());
+    /// let steps = vec![Run::Shell("true".into()), Run::Shell("".into())];
+    /// assert!(Run::Steps { steps, ignore_errors: false }.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Shell(command) => command.trim().is_empty(),
+            Self::Bare(command) => command.first().is_none_or(|run| run.trim().is_empty()),
+            Self::Steps { steps, .. } => steps.is_empty() || steps.iter().any(Self::is_empty),
+        }
+    }
+
+    /// The command as it would be written in a config.
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Shell(command) => Value::from(command.as_str()),
+            Self::Bare(command) => string_array(command),
+            Self::Steps { steps, .. } => Value::Array(steps.iter().map(Self::to_value).collect()),
+        }
+    }
 }
 
 impl Display for Run {
@@ -170,76 +913,454 @@ impl Display for Run {
                     Ok(())
                 }
             },
+            // Written like a shell would run them, so `--print` stays usable in a script.
+            Self::Steps {
+                steps,
+                ignore_errors,
+            } => {
+                let separator = if *ignore_errors { "; " } else { " && " };
+                for (i, step) in steps.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{separator}")?;
+                    }
+                    write!(f, "{step}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum Entry {
-    Full { name: ImStr, run: Run, group: i64 },
+    Full {
+        name: ImStr,
+        run: Run,
+        options: EntryOptions,
+    },
+    Submenu {
+        name: ImStr,
+        entries: Vec<Entry>,
+        /// The launcher prompt to show in the submenu, overriding `config.prompt`.
+        prompt: Option<ImStr>,
+        options: EntryOptions,
+    },
     Name(ImStr),
     Filter(ImStr),
 }
 
-impl Entry {
-    fn try_new(name: ImStr, entry: &Value) -> anyhow::Result<Self> {
-        match entry {
-            Value::Boolean(true) => Ok(Self::Name(name)),
-            Value::Boolean(false) => Ok(Self::Filter(name)),
-            Value::String(run) => Ok(Self::Full {
-                name,
-                run: Run::Shell(ImStr::from(run)),
-                group: 0,
-            }),
-            Value::Array(run) => {
-                let run = run
-                    .iter()
-                    .map(try_into_array_string(&format!("menu.{name}")))
-                    .collect::<Result<Vec<ImStr>, _>>()?;
-
-                Ok(Self::Full {
-                    name,
-                    run: Run::Bare(run),
-                    group: 0,
-                })
-            }
-            Value::Table(table) => {
-                let group = table
-                    .get("group")
-                    .map(try_into_integer(&format!("menu.{name}.group")))
-                    .transpose()?
-                    .unwrap_or(0);
+/// Settings a menu entry may specify in addition to its run command.
+#[derive(Debug, Default, Clone)]
+pub struct EntryOptions {
+    pub group: i64,
+    /// Directory to run the command in, already resolved against the config's directory.
+    pub cwd: Option<PathBuf>,
+    /// Environment variables to set for the command, overriding those in `config.env`.
+    pub env: Vec<(ImStr, ImStr)>,
+    /// Commands whose output, trimmed, is substituted for placeholders of the same name,
+    /// instead of prompting for them.
+    pub values: Vec<(ImStr, Run)>,
+    /// Whether to run the command in `config.terminal-command`; overrides `config.terminal`.
+    pub terminal: Option<bool>,
+    /// Whether to ask for confirmation with the launcher before running the command.
+    pub confirm: bool,
+    /// The prompt to ask for confirmation with, instead of the default.
+    pub confirm_prompt: Option<ImStr>,
+    /// Whether to leave the entry out of the menu.
+    pub hidden: bool,
+    /// Whether to show the entry without allowing it to be run.
+    pub disabled: bool,
+    /// The name or path of an icon to show with the entry, if the launcher supports icons.
+    pub icon: Option<ImStr>,
+    /// Text to show after the entry's name, unless `config.show-descriptions` is false.
+    pub description: Option<ImStr>,
+    /// A key hint to show in brackets before the entry's name, like `[q] Quit`.
+    pub key: Option<ImStr>,
+    /// The section of the menu to show the entry in, under a header with this name.
+    pub category: Option<ImStr>,
+    /// Other names that select the entry when they're typed, without being shown.
+    pub aliases: Vec<ImStr>,
+    /// A command that must succeed when the menu is built for the entry to be shown.
+    pub when: Option<Run>,
+    /// A command run before the entry's command, which is skipped if it succeeds,
+    /// like for an app that's already running.
+    pub check: Option<Run>,
+    /// When the entry is shown; it's shown if any of them contain the current time,
+    /// or always if there are none.
+    pub schedule: Vec<Schedule>,
+    /// Whether to show the menu again after running the entry, until it's cancelled.
+    pub sticky: bool,
+}
 
-                let missing_run_error = format!(
-                    "`{}` must have a value if `{}` is a table",
-                    style_stderr!(bold(), "menu.{name}.run"),
-                    style_stderr!(bold(), "menu.{name}"),
-                );
+impl EntryOptions {
+    /// Add the options that aren't their default to `table`, as they would be written in a config.
+    pub fn write_to(&self, table: &mut Map<String, Value>) {
+        let mut insert = |key: &str, value| {
+            table.insert(key.to_owned(), value);
+        };
 
-                table
-                    .get("run")
-                    .map(|value| match value {
-                        Value::Boolean(true) => Ok(Self::Name(name)),
-                        Value::Boolean(false) => Ok(Self::Filter(name)),
-                        Value::String(run) => Ok(Self::Full {
+        if self.group != 0 {
+            insert("group", Value::Integer(self.group));
+        }
+        if let Some(cwd) = &self.cwd {
+            insert("cwd", Value::from(cwd.to_string_lossy().as_ref()));
+        }
+        if !self.env.is_empty() {
+            insert("env", env_table(&self.env));
+        }
+        if !self.values.is_empty() {
+            insert("values", values_table(&self.values));
+        }
+        if let Some(terminal) = self.terminal {
+            insert("terminal", Value::Boolean(terminal));
+        }
+        if self.confirm {
+            insert("confirm", Value::Boolean(true));
+        }
+        if let Some(prompt) = &self.confirm_prompt {
+            insert("confirm-prompt", Value::from(prompt.as_str()));
+        }
+        if self.hidden {
+            insert("hidden", Value::Boolean(true));
+        }
+        if self.disabled {
+            insert("disabled", Value::Boolean(true));
+        }
+        if let Some(icon) = &self.icon {
+            insert("icon", Value::from(icon.as_str()));
+        }
+        if let Some(description) = &self.description {
+            insert("description", Value::from(description.as_str()));
+        }
+        if let Some(key) = &self.key {
+            insert("key", Value::from(key.as_str()));
+        }
+        if let Some(category) = &self.category {
+            insert("category", Value::from(category.as_str()));
+        }
+        if !self.aliases.is_empty() {
+            let aliases = self.aliases.iter().map(|alias| Value::from(alias.as_str()));
+            insert("aliases", Value::Array(aliases.collect()));
+        }
+        if let Some(when) = &self.when {
+            insert("when", when.to_value());
+        }
+        if let Some(check) = &self.check {
+            insert("check", check.to_value());
+        }
+        match self.schedule.as_slice() {
+            [] => {}
+            [schedule] => insert("schedule", Value::from(schedule.to_string())),
+            schedules => insert(
+                "schedule",
+                Value::Array(
+                    schedules
+                        .iter()
+                        .map(|s| Value::from(s.to_string()))
+                        .collect(),
+                ),
+            ),
+        }
+        if self.sticky {
+            insert("sticky", Value::Boolean(true));
+        }
+    }
+
+    fn try_new(key: &str, table: &Map<String, Value>, base: &Base) -> anyhow::Result<Self> {
+        let group = table
+            .get("group")
+            .map(try_into_integer(&format!("{key}.group")))
+            .transpose()?
+            .unwrap_or(0);
+
+        let cwd = table
+            .get("cwd")
+            .map(try_into_string(&format!("{key}.cwd")))
+            .transpose()?
+            .map(|cwd| match base {
+                Base::Dir(dir) => Ok(dir.join(cwd.as_str())),
+                Base::Url(_) if Path::new(cwd.as_str()).is_absolute() => {
+                    Ok(PathBuf::from(cwd.as_str()))
+                }
+                Base::Url(_) => Err(anyhow!(
+                    "`{}` must be an absolute path in a config downloaded from a url",
+                    style_stderr!(bold(), "{key}.cwd")
+                )),
+            })
+            .transpose()?;
+
+        let env = table
+            .get("env")
+            .map(try_into_env(&format!("{key}.env")))
+            .transpose()?
+            .unwrap_or_default();
+
+        let values = table
+            .get("values")
+            .map(try_into_values(&format!("{key}.values")))
+            .transpose()?
+            .unwrap_or_default();
+
+        let terminal = table
+            .get("terminal")
+            .map(try_into_boolean(&format!("{key}.terminal")))
+            .transpose()?;
+
+        let confirm = table
+            .get("confirm")
+            .map(try_into_boolean(&format!("{key}.confirm")))
+            .transpose()?
+            .unwrap_or(false);
+
+        let confirm_prompt = table
+            .get("confirm-prompt")
+            .map(try_into_string(&format!("{key}.confirm-prompt")))
+            .transpose()?;
+
+        let hidden = table
+            .get("hidden")
+            .map(try_into_boolean(&format!("{key}.hidden")))
+            .transpose()?
+            .unwrap_or(false);
+
+        let disabled = table
+            .get("disabled")
+            .map(try_into_boolean(&format!("{key}.disabled")))
+            .transpose()?
+            .unwrap_or(false);
+
+        let icon = table
+            .get("icon")
+            .map(try_into_string(&format!("{key}.icon")))
+            .transpose()?;
+
+        let description = table
+            .get("description")
+            .map(try_into_string(&format!("{key}.description")))
+            .transpose()?;
+
+        let key_hint = table
+            .get("key")
+            .map(try_into_string(&format!("{key}.key")))
+            .transpose()?;
+
+        let category = table
+            .get("category")
+            .map(try_into_string(&format!("{key}.category")))
+            .transpose()?;
+
+        let aliases = match table.get("aliases") {
+            Some(aliases) => try_into_array(&format!("{key}.aliases"))(aliases)?
+                .iter()
+                .map(try_into_array_string(&format!("{key}.aliases")))
+                .collect::<Result<Vec<ImStr>, _>>()?,
+            None => Vec::new(),
+        };
+
+        let when = table
+            .get("when")
+            .map(try_into_run(&format!("{key}.when")))
+            .transpose()?;
+
+        let check = table
+            .get("check")
+            .map(try_into_run(&format!("{key}.check")))
+            .transpose()?;
+
+        let schedule = table
+            .get("schedule")
+            .map(try_into_schedules(&format!("{key}.schedule")))
+            .transpose()?
+            .unwrap_or_default();
+
+        let sticky = table
+            .get("sticky")
+            .map(try_into_boolean(&format!("{key}.sticky")))
+            .transpose()?
+            .unwrap_or(false);
+
+        Ok(Self {
+            group,
+            cwd,
+            env,
+            values,
+            terminal,
+            confirm,
+            confirm_prompt,
+            hidden,
+            disabled,
+            icon,
+            description,
+            key: key_hint,
+            category,
+            aliases,
+            when,
+            check,
+            schedule,
+            sticky,
+        })
+    }
+}
+
+impl Entry {
+    /// Parse the entry `name`, found in the config at the dotted path `key`.
+    ///
+    /// An empty command is an error, since it would run without doing anything,
+    /// which is likely a mistake, like a variable that expanded to nothing.
+    fn try_new(
+        name: ImStr,
+        key: &str,
+        entry: &Value,
+        base: &Base,
+        opener: &Opener,
+    ) -> anyhow::Result<Self> {
+        let entry = Self::try_parse(name, key, entry, base, opener)?;
+        if let Self::Full { run, .. } = &entry {
+            if run.is_empty() {
+                return Err(anyhow!(
+                    "`{}` has an empty command",
+                    style_stderr!(bold(), "{key}")
+                ));
+            }
+        }
+
+        Ok(entry)
+    }
+
+    fn try_parse(
+        name: ImStr,
+        key: &str,
+        entry: &Value,
+        base: &Base,
+        opener: &Opener,
+    ) -> anyhow::Result<Self> {
+        match entry {
+            Value::Boolean(true) => Ok(Self::Name(name)),
+            Value::Boolean(false) => Ok(Self::Filter(name)),
+            Value::String(run) => Ok(Self::Full {
+                name,
+                run: Run::Shell(ImStr::from(run)),
+                options: EntryOptions::default(),
+            }),
+            Value::Array(run) => {
+                let run = run
+                    .iter()
+                    .map(try_into_array_string(key))
+                    .collect::<Result<Vec<ImStr>, _>>()?;
+
+                Ok(Self::Full {
+                    name,
+                    run: Run::Bare(run),
+                    options: EntryOptions::default(),
+                })
+            }
+            Value::Table(table) => {
+                let options = EntryOptions::try_new(key, table, base)?;
+
+                let exclusive = ["run", "args", "steps", "url", "submenu"]
+                    .into_iter()
+                    .filter(|exclusive| table.contains_key(*exclusive))
+                    .collect::<Vec<&str>>();
+                if let [first, second, ..] = exclusive.as_slice() {
+                    return Err(anyhow!(
+                        "`{}` and `{}` can't both have a value",
+                        style_stderr!(bold(), "{key}.{first}"),
+                        style_stderr!(bold(), "{key}.{second}"),
+                    ));
+                }
+
+                if let Some(submenu) = table.get("submenu") {
+                    let entries =
+                        try_get_submenu(&format!("{key}.submenu"), submenu, base, opener)?;
+                    let prompt = table
+                        .get("prompt")
+                        .map(try_into_string(&format!("{key}.prompt")))
+                        .transpose()?;
+                    return Ok(Self::Submenu {
+                        name,
+                        entries,
+                        prompt,
+                        options,
+                    });
+                }
+
+                if let Some(args) = table.get("args") {
+                    let args = try_into_array(&format!("{key}.args"))(args)?
+                        .iter()
+                        .map(try_into_array_string(&format!("{key}.args")))
+                        .collect::<Result<Vec<ImStr>, _>>()?;
+                    return Ok(Self::Full {
+                        name,
+                        run: Run::Bare(args),
+                        options,
+                    });
+                }
+
+                if let Some(url) = table.get("url") {
+                    let url = try_into_string(&format!("{key}.url"))(url)?;
+                    let run = opener.0.iter().cloned().chain([url]).collect();
+                    return Ok(Self::Full {
+                        name,
+                        run: Run::Bare(run),
+                        options,
+                    });
+                }
+
+                if let Some(steps) = table.get("steps") {
+                    let steps_key = format!("{key}.steps");
+                    let steps = try_into_array(&steps_key)(steps)?
+                        .iter()
+                        .map(try_into_run(&steps_key))
+                        .collect::<anyhow::Result<Vec<Run>>>()?;
+                    let ignore_errors = table
+                        .get("ignore-errors")
+                        .map(try_into_boolean(&format!("{key}.ignore-errors")))
+                        .transpose()?
+                        .unwrap_or(false);
+                    return Ok(Self::Full {
+                        name,
+                        run: Run::Steps {
+                            steps,
+                            ignore_errors,
+                        },
+                        options,
+                    });
+                }
+
+                let missing_run_error = format!(
+                    "`{}`, `{}`, `{}`, `{}`, or `{}` must have a value if `{}` is a table",
+                    style_stderr!(bold(), "{key}.run"),
+                    style_stderr!(bold(), "{key}.args"),
+                    style_stderr!(bold(), "{key}.steps"),
+                    style_stderr!(bold(), "{key}.url"),
+                    style_stderr!(bold(), "{key}.submenu"),
+                    style_stderr!(bold(), "{key}"),
+                );
+
+                table
+                    .get("run")
+                    .map(|value| match value {
+                        Value::Boolean(true) => Ok(Self::Name(name)),
+                        Value::Boolean(false) => Ok(Self::Filter(name)),
+                        Value::String(run) => Ok(Self::Full {
                             name,
                             run: Run::Shell(ImStr::from(run)),
-                            group,
+                            options,
                         }),
                         Value::Array(run) => {
                             let run = run
                                 .iter()
-                                .map(try_into_array_string(&format!("menu.{name}.run")))
+                                .map(try_into_array_string(&format!("{key}.run")))
                                 .collect::<Result<Vec<ImStr>, _>>()?;
 
                             Ok(Self::Full {
                                 name,
                                 run: Run::Bare(run),
-                                group,
+                                options,
                             })
                         }
                         other => type_error(
-                            "menu.{name}.run",
+                            &format!("{key}.run"),
                             &["string", "array", "boolean"],
                             other.type_str(),
                         ),
@@ -248,7 +1369,7 @@ impl Entry {
                     .context(missing_run_error)
             }
             other => type_error(
-                "menu.{name}",
+                key,
                 &["string", "array", "boolean", "table"],
                 other.type_str(),
             ),
@@ -257,11 +1378,50 @@ impl Entry {
 
     pub fn name(&self) -> ImStr {
         match self {
-            Self::Full { name, .. } | Self::Name(name) | Self::Filter(name) => name.clone(),
+            Self::Full { name, .. }
+            | Self::Submenu { name, .. }
+            | Self::Name(name)
+            | Self::Filter(name) => name.clone(),
         }
     }
 }
 
+/// Parse a submenu, which is either a table like `menu`,
+/// or an array of tables that each have a `name`.
+fn try_get_submenu(
+    key: &str,
+    submenu: &Value,
+    base: &Base,
+    opener: &Opener,
+) -> anyhow::Result<Vec<Entry>> {
+    match submenu {
+        Value::Table(table) => table
+            .iter()
+            .map(|(name, value)| {
+                let key = format!("{key}.{name}");
+                Entry::try_new(ImStr::from(name), &key, value, base, opener)
+            })
+            .collect(),
+        Value::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let key = format!("{key}[{i}]");
+                let name = try_into_table(&key)(value)?
+                    .get("name")
+                    .map(try_into_string(&format!("{key}.name")))
+                    .transpose()?
+                    .context(format!(
+                        "`{}` must have a value",
+                        style_stderr!(bold(), "{key}.name")
+                    ))?;
+                Entry::try_new(name, &key, value, base, opener)
+            })
+            .collect(),
+        other => type_error(key, &["table", "array"], other.type_str()),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Shell {
     Disabled,
@@ -275,21 +1435,88 @@ impl Shell {
             Self::Enabled { .. } => true,
         }
     }
+
+    /// Replace the arguments after the shell program with `args`, if they're set.
+    fn with_args(mut self, args: &ShellArgs) -> Self {
+        if let (Self::Enabled { shell, .. }, Some(args)) = (&mut self, &args.0) {
+            if !shell.is_empty() {
+                shell.truncate(1);
+                shell.extend(args.iter().cloned());
+            }
+        }
+        self
+    }
+
+    /// Check that the shell is an executable file, or is the name of one on `PATH`.
+    fn validate(&self) -> anyhow::Result<()> {
+        let program = match self {
+            Self::Enabled { shell, .. } => match shell.first() {
+                Some(program) => program.as_str(),
+                None => return Ok(()),
+            },
+            Self::Disabled => return Ok(()),
+        };
+
+        if is_program(program) {
+            Ok(())
+        } else if program.contains('/') {
+            Err(anyhow!(
+                "the shell `{}` in `{}` isn't an executable file",
+                style_stderr!(bold(), "{program}"),
+                style_stderr!(bold(), "config.shell"),
+            ))
+        } else {
+            Err(anyhow!(
+                "the shell `{}` in `{}` wasn't found in `{}`",
+                style_stderr!(bold(), "{program}"),
+                style_stderr!(bold(), "config.shell"),
+                style_stderr!(bold(), "PATH"),
+            ))
+        }
+    }
+}
+
+/// Whether `program` is a path to an executable file, or the name of one on `PATH`.
+fn is_program(program: &str) -> bool {
+    if program.contains('/') {
+        Path::new(program).is_executable()
+    } else {
+        env::var_os("PATH").is_some_and(|paths| {
+            env::split_paths(&paths).any(|path| path.join(program).is_executable())
+        })
+    }
 }
 
 impl ConfigItem for Shell {
+    const KEYS: Option<&'static [&'static str]> = Some(&["shell", "piped"]);
+
     fn name() -> &'static str {
         "shell"
     }
     fn merge(self, _: Self) -> Self {
         self
     }
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Self::Disabled => Some(Value::Boolean(false)),
+            Self::Enabled { shell, piped } => Some(table([
+                ("shell", string_array(shell)),
+                ("piped", Value::Boolean(*piped)),
+            ])),
+        }
+    }
 }
 
 impl Default for Shell {
+    /// `$SHELL -c`, or `sh -c` if `$SHELL` is unset or empty.
     fn default() -> Self {
+        let shell = env::var("SHELL")
+            .ok()
+            .filter(|shell| !shell.is_empty())
+            .map_or_else(|| ImStr::new("sh"), ImStr::from);
+
         Self::Enabled {
-            shell: vec![ImStr::new("sh"), ImStr::new("-c")],
+            shell: vec![shell, ImStr::new("-c")],
             piped: false,
         }
     }
@@ -343,8 +1570,32 @@ impl TryFrom<&Value> for Shell {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The arguments passed to the shell before the command, instead of those from `config.shell`.
+#[derive(Debug, Default, Clone)]
+pub struct ShellArgs(pub Option<Vec<ImStr>>);
+
+impl ConfigItem for ShellArgs {
+    fn name() -> &'static str {
+        "shell-args"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        self.0.as_deref().map(string_array)
+    }
+}
+
+impl TryFrom<&Value> for ShellArgs {
+    type Error = anyhow::Error;
+    fn try_from(args: &Value) -> anyhow::Result<Self> {
+        try_into_args("config.shell-args")(args).map(|args| Self(Some(args)))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub enum Custom {
+    #[default]
     Disabled,
     Enabled,
 }
@@ -356,11 +1607,8 @@ impl ConfigItem for Custom {
     fn merge(self, _: Self) -> Self {
         self
     }
-}
-
-impl Default for Custom {
-    fn default() -> Self {
-        Self::Disabled
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(matches!(self, Self::Enabled)))
     }
 }
 
@@ -368,27 +1616,224 @@ impl TryFrom<&Value> for Custom {
     type Error = anyhow::Error;
     fn try_from(custom: &Value) -> anyhow::Result<Self> {
         if try_into_boolean("config.custom")(custom)? {
-            Ok(Self::Disabled)
-        } else {
             Ok(Self::Enabled)
+        } else {
+            Ok(Self::Disabled)
+        }
+    }
+}
+
+/// Text that typed input must start with to be run as an ad-hoc command.
+#[derive(Debug, Default, Clone)]
+pub struct AdHocPrefix(pub Option<ImStr>);
+
+impl AdHocPrefix {
+    /// The ad-hoc command in `choice`, without the prefix, or `None` if it doesn't have it.
+    ///
+    /// ```
+    /// use dmm::config::AdHocPrefix;
+    ///
+    /// let prefix = AdHocPrefix(Some("$ ".into()));
+    /// assert_eq!(prefix.strip("$ htop"), Some("htop"));
+    /// assert_eq!(prefix.strip("htpo"), None);
+    /// assert_eq!(AdHocPrefix(None).strip("htop"), Some("htop"));
+    /// ```
+    pub fn strip<'a>(&self, choice: &'a str) -> Option<&'a str> {
+        match &self.0 {
+            Some(prefix) => choice.strip_prefix(prefix.as_str()),
+            None => Some(choice),
         }
     }
 }
 
+impl ConfigItem for AdHocPrefix {
+    fn name() -> &'static str {
+        "ad-hoc-prefix"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        self.0.as_deref().map(Value::from)
+    }
+}
+
+impl TryFrom<&Value> for AdHocPrefix {
+    type Error = anyhow::Error;
+    fn try_from(prefix: &Value) -> anyhow::Result<Self> {
+        let prefix = try_into_string("config.ad-hoc-prefix")(prefix)?;
+        Ok(Self((!prefix.is_empty()).then_some(prefix)))
+    }
+}
+
+/// Whether to ask with the launcher before running an ad-hoc command, like `confirm` for entries.
+#[derive(Debug, Default, Clone)]
+pub struct ConfirmAdHoc(pub bool);
+
+impl ConfigItem for ConfirmAdHoc {
+    fn name() -> &'static str {
+        "confirm-ad-hoc"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for ConfirmAdHoc {
+    type Error = anyhow::Error;
+    fn try_from(confirm: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.confirm-ad-hoc")(confirm).map(Self)
+    }
+}
+
+/// Whether to show the selected commands in the launcher to edit them before they're run.
+#[derive(Debug, Default, Clone)]
+pub struct Editable(pub bool);
+
+impl ConfigItem for Editable {
+    fn name() -> &'static str {
+        "editable"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for Editable {
+    type Error = anyhow::Error;
+    fn try_from(editable: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.editable")(editable).map(Self)
+    }
+}
+
+/// Whether to wait for commands and log what they output, with `--verbose`.
+#[derive(Debug, Default, Clone)]
+pub struct Capture(pub bool);
+
+impl ConfigItem for Capture {
+    fn name() -> &'static str {
+        "capture"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for Capture {
+    type Error = anyhow::Error;
+    fn try_from(capture: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.capture")(capture).map(Self)
+    }
+}
+
+/// Whether to replace `dmm` with the selected command, if only one is selected.
+#[derive(Debug, Default, Clone)]
+pub struct Exec(pub bool);
+
+impl ConfigItem for Exec {
+    fn name() -> &'static str {
+        "exec"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for Exec {
+    type Error = anyhow::Error;
+    fn try_from(exec: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.exec")(exec).map(Self)
+    }
+}
+
+/// Whether ad-hoc commands are run in the shell, or split into words and run directly.
 #[derive(Debug, Clone)]
+pub struct AdHocShell(pub bool);
+
+impl ConfigItem for AdHocShell {
+    fn name() -> &'static str {
+        "ad-hoc-shell"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl Default for AdHocShell {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+impl TryFrom<&Value> for AdHocShell {
+    type Error = anyhow::Error;
+    fn try_from(ad_hoc_shell: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.ad-hoc-shell")(ad_hoc_shell).map(Self)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub enum Numbered {
+    #[default]
     Disabled,
     Enabled(Separator),
 }
 
 impl Numbered {
+    /// The separator between an entry's tag and name; an empty `separator` means none.
+    ///
+    /// ```
+    /// use dmm::config::Numbered;
+    ///
+    /// let separator = |numbered: &str| {
+    ///     let numbered = numbered.parse::<toml::Value>().unwrap();
+    ///     Numbered::try_from(&numbered["numbered"]).unwrap().separator().to_owned()
+    /// };
+    ///
+    /// assert_eq!(separator("numbered = true"), ": ");
+    /// assert_eq!(separator("numbered = { numbered = true }"), ": ");
+    /// assert_eq!(separator("numbered = { numbered = true, separator = '' }"), "");
+    /// assert_eq!(separator("numbered = { numbered = true, separator = false }"), "");
+    /// assert_eq!(separator("numbered = { numbered = true, separator = ' | ' }"), " | ");
+    /// assert_eq!(separator("numbered = { numbered = true, separator = ' \u{2014} ' }"), " \u{2014} ");
+    ///
+    /// // Each entry must stay on one line, with one tag.
+    /// let newline = r#"numbered = { numbered = true, separator = "\n" }"#;
+    /// let newline = newline.parse::<toml::Value>().unwrap();
+    /// assert!(Numbered::try_from(&newline["numbered"]).is_err());
+    /// ```
     pub fn separator(&self) -> &str {
         match self {
-            Self::Disabled | Self::Enabled(Separator::Disabled) => "",
+            Self::Disabled
+            | Self::Enabled(Separator::Disabled)
+            | Self::Enabled(Separator::Format(_)) => "",
             Self::Enabled(Separator::Enabled(separator)) => separator.as_str(),
         }
     }
 
+    /// The template to display each entry with, containing `{tag}` and maybe `{name}`.
+    pub fn format(&self) -> Option<&str> {
+        match self {
+            Self::Enabled(Separator::Format(format)) => Some(format.as_str()),
+            _ => None,
+        }
+    }
+
     pub const fn is_enabled(&self) -> bool {
         match self {
             Self::Disabled => false,
@@ -398,17 +1843,26 @@ impl Numbered {
 }
 
 impl ConfigItem for Numbered {
+    const KEYS: Option<&'static [&'static str]> = Some(&["numbered", "separator", "format"]);
+
     fn name() -> &'static str {
         "numbered"
     }
     fn merge(self, _: Self) -> Self {
         self
     }
-}
-
-impl Default for Numbered {
-    fn default() -> Self {
-        Self::Disabled
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Self::Disabled => Some(Value::Boolean(false)),
+            Self::Enabled(separator) => Some(table([
+                ("numbered", Value::Boolean(true)),
+                match separator {
+                    Separator::Disabled => ("separator", Value::Boolean(false)),
+                    Separator::Enabled(separator) => ("separator", Value::from(separator.as_str())),
+                    Separator::Format(format) => ("format", Value::from(format.as_str())),
+                },
+            ])),
+        }
     }
 }
 
@@ -431,6 +1885,18 @@ impl TryFrom<&Value> for Numbered {
                     .transpose()?
                     .unwrap_or_default();
 
+                let separator = match numbered.get("format") {
+                    Some(_) if numbered.contains_key("separator") => {
+                        return Err(anyhow!(
+                            "`{}` and `{}` can't both have a value",
+                            style_stderr!(bold(), "config.numbered.separator"),
+                            style_stderr!(bold(), "config.numbered.format"),
+                        ))
+                    }
+                    Some(format) => Separator::try_format(format)?,
+                    None => separator,
+                };
+
                 if enabled {
                     Ok(Self::Enabled(separator))
                 } else {
@@ -442,25 +1908,318 @@ impl TryFrom<&Value> for Numbered {
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum Separator {
-    Disabled,
-    Enabled(ImStr),
+/// The tags used to number menu entries if `config.numbered` is enabled.
+#[derive(Debug, Default, Clone, Copy)]
+pub enum Tags {
+    #[default]
+    Decimal,
+    Alpha,
+    Hex,
 }
 
-impl Default for Separator {
-    fn default() -> Self {
-        Self::Enabled(ImStr::new(": "))
-    }
+impl Tags {
+    const VALUES: &'static [&'static str] = &["decimal", "alpha", "hex"];
 }
 
-impl TryFrom<&Value> for Separator {
-    type Error = anyhow::Error;
-    fn try_from(separator: &Value) -> anyhow::Result<Self> {
-        match separator {
+impl ConfigItem for Tags {
+    fn name() -> &'static str {
+        "tags"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        let tags = match self {
+            Self::Decimal => "decimal",
+            Self::Alpha => "alpha",
+            Self::Hex => "hex",
+        };
+        Some(Value::from(tags))
+    }
+}
+
+impl TryFrom<&Value> for Tags {
+    type Error = anyhow::Error;
+    fn try_from(tags: &Value) -> anyhow::Result<Self> {
+        let tags = try_into_string("config.tags")(tags)?;
+        match tags.as_str() {
+            "decimal" => Ok(Self::Decimal),
+            "alpha" => Ok(Self::Alpha),
+            "hex" => Ok(Self::Hex),
+            other => Err(anyhow!(
+                "`{}` can't be `{}`; valid values are: {}",
+                style_stderr!(bold(), "config.tags"),
+                style_stderr!(bold(), "{other}"),
+                Self::VALUES
+                    .iter()
+                    .map(|value| format!("`{}`", style_stderr!(bold(), "{value}")))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// How entries in the same group, selected as often as each other, are ordered.
+#[derive(Debug, Default, Clone, Copy)]
+pub enum Sort {
+    /// By name, ignoring case.
+    #[default]
+    Alpha,
+    /// By name, ignoring case, from `z` to `a`.
+    AlphaDesc,
+    /// In the order they're read from the config, `config.path`, and `config.source`.
+    None,
+}
+
+impl Sort {
+    const VALUES: &'static [&'static str] = &["alpha", "alpha-desc", "none"];
+}
+
+impl ConfigItem for Sort {
+    fn name() -> &'static str {
+        "sort"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        let sort = match self {
+            Self::Alpha => "alpha",
+            Self::AlphaDesc => "alpha-desc",
+            Self::None => "none",
+        };
+        Some(Value::from(sort))
+    }
+}
+
+impl TryFrom<&Value> for Sort {
+    type Error = anyhow::Error;
+    fn try_from(sort: &Value) -> anyhow::Result<Self> {
+        let sort = try_into_string("config.sort")(sort)?;
+        match sort.as_str() {
+            "alpha" => Ok(Self::Alpha),
+            "alpha-desc" => Ok(Self::AlphaDesc),
+            "none" => Ok(Self::None),
+            other => Err(anyhow!(
+                "`{}` can't be `{}`; valid values are: {}",
+                style_stderr!(bold(), "config.sort"),
+                style_stderr!(bold(), "{other}"),
+                Self::VALUES
+                    .iter()
+                    .map(|value| format!("`{}`", style_stderr!(bold(), "{value}")))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// Whether to pad tags to the same width if `config.numbered` is enabled.
+#[derive(Debug, Default, Clone)]
+pub struct TagPadding(pub bool);
+
+impl ConfigItem for TagPadding {
+    fn name() -> &'static str {
+        "tag-padding"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for TagPadding {
+    type Error = anyhow::Error;
+    fn try_from(padding: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.tag-padding")(padding).map(Self)
+    }
+}
+
+/// Whether to add tags to the menu; without them, entries are found by name.
+#[derive(Debug, Clone)]
+pub struct ShowTags(pub bool);
+
+impl ConfigItem for ShowTags {
+    fn name() -> &'static str {
+        "show-tags"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl Default for ShowTags {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+impl TryFrom<&Value> for ShowTags {
+    type Error = anyhow::Error;
+    fn try_from(show_tags: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.show-tags")(show_tags).map(Self)
+    }
+}
+
+/// Whether to show entries' descriptions after their names.
+#[derive(Debug, Clone)]
+pub struct ShowDescriptions(pub bool);
+
+impl ConfigItem for ShowDescriptions {
+    fn name() -> &'static str {
+        "show-descriptions"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl Default for ShowDescriptions {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+impl TryFrom<&Value> for ShowDescriptions {
+    type Error = anyhow::Error;
+    fn try_from(show_descriptions: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.show-descriptions")(show_descriptions).map(Self)
+    }
+}
+
+/// Whether to match tags and typed names case insensitively.
+#[derive(Debug, Default, Clone)]
+pub struct CaseInsensitive(pub bool);
+
+impl ConfigItem for CaseInsensitive {
+    fn name() -> &'static str {
+        "case-insensitive"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for CaseInsensitive {
+    type Error = anyhow::Error;
+    fn try_from(case_insensitive: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.case-insensitive")(case_insensitive).map(Self)
+    }
+}
+
+/// Whether to select the only entry that fuzzily matches typed text, instead of running it.
+#[derive(Debug, Default, Clone)]
+pub struct Fuzzy(pub bool);
+
+impl ConfigItem for Fuzzy {
+    fn name() -> &'static str {
+        "fuzzy"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for Fuzzy {
+    type Error = anyhow::Error;
+    fn try_from(fuzzy: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.fuzzy")(fuzzy).map(Self)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Separator {
+    Disabled,
+    Enabled(ImStr),
+    /// A template for the whole line, from `config.numbered.format`.
+    Format(ImStr),
+}
+
+impl Separator {
+    fn try_format(format: &Value) -> anyhow::Result<Self> {
+        let format = try_into_string("config.numbered.format")(format)?;
+        check_separator("config.numbered.format", &format)?;
+
+        let mut has_tag = false;
+        template::render(&format, |placeholder| match placeholder {
+            "tag" => {
+                has_tag = true;
+                Ok(ImStr::new(""))
+            }
+            "name" => Ok(ImStr::new("")),
+            other => Err(anyhow!(
+                "unknown placeholder `{}`; valid placeholders are `{}` and `{}`",
+                style_stderr!(bold(), "{{{other}}}"),
+                style_stderr!(bold(), "{{tag}}"),
+                style_stderr!(bold(), "{{name}}"),
+            )),
+        })
+        .context(format!(
+            "`{}` is invalid",
+            style_stderr!(bold(), "config.numbered.format")
+        ))?;
+
+        if !has_tag {
+            return Err(anyhow!(
+                "`{}` must contain `{}`, so the selected entry can be found",
+                style_stderr!(bold(), "config.numbered.format"),
+                style_stderr!(bold(), "{{tag}}"),
+            ));
+        }
+
+        Ok(Self::Format(format))
+    }
+}
+
+/// Check that text added to each line of the menu keeps it one line, with one tag.
+fn check_separator(name: &str, separator: &str) -> anyhow::Result<()> {
+    if separator.contains('\n') {
+        return Err(anyhow!(
+            "`{}` can't contain a newline, since each entry must be one line",
+            style_stderr!(bold(), "{name}")
+        ));
+    }
+    if separator.contains(tag::SEP) {
+        return Err(anyhow!(
+            "`{}` can't contain a zero width non joiner, since it marks the start and end of tags",
+            style_stderr!(bold(), "{name}")
+        ));
+    }
+    Ok(())
+}
+
+impl Default for Separator {
+    fn default() -> Self {
+        Self::Enabled(ImStr::new(": "))
+    }
+}
+
+impl TryFrom<&Value> for Separator {
+    type Error = anyhow::Error;
+    fn try_from(separator: &Value) -> anyhow::Result<Self> {
+        match separator {
             Value::Boolean(false) => Ok(Self::Disabled),
             Value::Boolean(true) => Ok(Self::default()),
-            Value::String(separator) => Ok(Self::Enabled(ImStr::from(separator))),
+            Value::String(separator) if separator.is_empty() => Ok(Self::Disabled),
+            Value::String(separator) => {
+                check_separator("config.numbered.separator", separator)?;
+                Ok(Self::Enabled(ImStr::from(separator)))
+            }
             other => type_error(
                 "config.numbered.separator",
                 &["boolean", "string"],
@@ -470,8 +2229,9 @@ impl TryFrom<&Value> for Separator {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone)]
 pub enum BinPath {
+    #[default]
     Disabled,
     Enabled {
         path: Vec<ImStr>,
@@ -483,17 +2243,32 @@ pub enum BinPath {
 }
 
 impl ConfigItem for BinPath {
+    const KEYS: Option<&'static [&'static str]> =
+        Some(&["path", "env", "replace", "recursive", "group"]);
+
     fn name() -> &'static str {
         "path"
     }
     fn merge(self, _: Self) -> Self {
         self
     }
-}
-
-impl Default for BinPath {
-    fn default() -> Self {
-        Self::Disabled
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Self::Disabled => Some(Value::Boolean(false)),
+            Self::Enabled {
+                path,
+                env,
+                replace,
+                recursive,
+                group,
+            } => Some(table([
+                ("path", string_array(path)),
+                ("env", Value::Boolean(*env)),
+                ("replace", Value::Boolean(*replace)),
+                ("recursive", Value::Boolean(*recursive)),
+                ("group", Value::Integer(*group)),
+            ])),
+        }
     }
 }
 
@@ -537,44 +2312,1078 @@ impl TryFrom<&Value> for BinPath {
                     .transpose()?
                     .unwrap_or_default();
 
-                let env = table
-                    .get("env")
-                    .map(try_into_boolean("config.path.env"))
-                    .transpose()?
-                    .unwrap_or(false);
+                let env = table
+                    .get("env")
+                    .map(try_into_boolean("config.path.env"))
+                    .transpose()?
+                    .unwrap_or(false);
+
+                let replace = table
+                    .get("replace")
+                    .map(try_into_boolean("config.path.replace"))
+                    .transpose()?
+                    .unwrap_or(false);
+
+                let recursive = table
+                    .get("recursive")
+                    .map(try_into_boolean("config.path.recursive"))
+                    .transpose()?
+                    .unwrap_or(false);
+
+                let group = table
+                    .get("group")
+                    .map(try_into_integer("config.path.group"))
+                    .transpose()?
+                    .unwrap_or(0);
+
+                Ok(Self::Enabled {
+                    path,
+                    env,
+                    replace,
+                    recursive,
+                    group,
+                })
+            }
+            other => type_error(
+                "config.numbered.separator",
+                &["boolean", "array", "table"],
+                other.type_str(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Env(pub Vec<(ImStr, ImStr)>);
+
+impl ConfigItem for Env {
+    const KEYS: Option<&'static [&'static str]> = None;
+
+    fn name() -> &'static str {
+        "env"
+    }
+    fn merge(mut self, default: Self) -> Self {
+        let keys = self
+            .0
+            .iter()
+            .map(|(key, _)| key.clone())
+            .collect::<HashSet<ImStr>>();
+        self.0
+            .extend(default.0.into_iter().filter(|(key, _)| !keys.contains(key)));
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(env_table(&self.0))
+    }
+}
+
+impl TryFrom<&Value> for Env {
+    type Error = anyhow::Error;
+    fn try_from(env: &Value) -> anyhow::Result<Self> {
+        try_into_env("config.env")(env).map(Self)
+    }
+}
+
+/// Whether to expand environment variables like `$HOME` in the config's strings when it's loaded.
+#[derive(Debug, Default, Clone)]
+pub enum ExpandEnv {
+    #[default]
+    Disabled,
+    Enabled {
+        /// Whether an undefined variable is an error, instead of expanding to nothing.
+        strict: bool,
+    },
+}
+
+impl ExpandEnv {
+    /// Expand the strings in `value`, which is found at the dotted path `key`.
+    fn expand(strict: bool, key: &str, value: &Value) -> anyhow::Result<Value> {
+        match value {
+            Value::String(string) => template::expand_vars(string, strict)
+                .map(Value::String)
+                .with_context(|| format!("unable to expand `{}`", style_stderr!(bold(), "{key}"))),
+            Value::Array(array) => array
+                .iter()
+                .enumerate()
+                .map(|(i, value)| Self::expand(strict, &format!("{key}[{i}]"), value))
+                .collect::<anyhow::Result<_>>()
+                .map(Value::Array),
+            Value::Table(table) => table
+                .iter()
+                .map(|(name, value)| {
+                    let key = match key {
+                        "" => name.clone(),
+                        key => format!("{key}.{name}"),
+                    };
+                    let value = Self::expand(strict, &key, value)?;
+                    Ok((name.clone(), value))
+                })
+                .collect::<anyhow::Result<_>>()
+                .map(Value::Table),
+            other => Ok(other.clone()),
+        }
+    }
+}
+
+impl ConfigItem for ExpandEnv {
+    const KEYS: Option<&'static [&'static str]> = Some(&["expand-env", "strict"]);
+
+    fn name() -> &'static str {
+        "expand-env"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Self::Disabled => Some(Value::Boolean(false)),
+            Self::Enabled { strict } => Some(table([
+                ("expand-env", Value::Boolean(true)),
+                ("strict", Value::Boolean(*strict)),
+            ])),
+        }
+    }
+}
+
+impl TryFrom<&Value> for ExpandEnv {
+    type Error = anyhow::Error;
+    fn try_from(expand: &Value) -> anyhow::Result<Self> {
+        match expand {
+            Value::Boolean(false) => Ok(Self::Disabled),
+            Value::Boolean(true) => Ok(Self::Enabled { strict: false }),
+            Value::Table(table) => {
+                let enabled = table
+                    .get("expand-env")
+                    .map(try_into_boolean("config.expand-env.expand-env"))
+                    .transpose()?
+                    .unwrap_or(false);
+
+                let strict = table
+                    .get("strict")
+                    .map(try_into_boolean("config.expand-env.strict"))
+                    .transpose()?
+                    .unwrap_or(false);
+
+                if enabled {
+                    Ok(Self::Enabled { strict })
+                } else {
+                    Ok(Self::Disabled)
+                }
+            }
+            other => type_error("config.expand-env", &["boolean", "table"], other.type_str()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Terminal(pub bool);
+
+impl ConfigItem for Terminal {
+    fn name() -> &'static str {
+        "terminal"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for Terminal {
+    type Error = anyhow::Error;
+    fn try_from(terminal: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.terminal")(terminal).map(Self)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TerminalCommand(pub Vec<ImStr>);
+
+impl ConfigItem for TerminalCommand {
+    fn name() -> &'static str {
+        "terminal-command"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(string_array(&self.0))
+    }
+}
+
+impl Default for TerminalCommand {
+    fn default() -> Self {
+        Self(vec![ImStr::new("xterm"), ImStr::new("-e")])
+    }
+}
+
+impl TryFrom<&Value> for TerminalCommand {
+    type Error = anyhow::Error;
+    fn try_from(command: &Value) -> anyhow::Result<Self> {
+        match command {
+            Value::String(command) => {
+                Ok(Self(command.split_whitespace().map(ImStr::from).collect()))
+            }
+            Value::Array(command) => command
+                .iter()
+                .map(try_into_array_string("config.terminal-command"))
+                .collect::<Result<Vec<ImStr>, _>>()
+                .map(Self),
+            other => type_error(
+                "config.terminal-command",
+                &["string", "array"],
+                other.type_str(),
+            ),
+        }
+    }
+}
+
+/// The program that entries with a `url` open it with, followed by the url.
+#[derive(Debug, Clone)]
+pub struct Opener(pub Vec<ImStr>);
+
+impl ConfigItem for Opener {
+    fn name() -> &'static str {
+        "opener"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(string_array(&self.0))
+    }
+}
+
+impl Default for Opener {
+    fn default() -> Self {
+        Self(vec![ImStr::new("xdg-open")])
+    }
+}
+
+impl TryFrom<&Value> for Opener {
+    type Error = anyhow::Error;
+    fn try_from(opener: &Value) -> anyhow::Result<Self> {
+        let opener = try_into_args("config.opener")(opener)?;
+        if opener.is_empty() {
+            return Err(anyhow!(
+                "`{}` must have a program to open urls with",
+                style_stderr!(bold(), "config.opener")
+            ));
+        }
+        Ok(Self(opener))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub enum Wait {
+    #[default]
+    Disabled,
+    Enabled {
+        keep_going: bool,
+    },
+}
+
+impl Wait {
+    pub const fn is_enabled(&self) -> bool {
+        match self {
+            Self::Disabled => false,
+            Self::Enabled { .. } => true,
+        }
+    }
+}
+
+impl ConfigItem for Wait {
+    const KEYS: Option<&'static [&'static str]> = Some(&["wait", "keep-going"]);
+
+    fn name() -> &'static str {
+        "wait"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Self::Disabled => Some(Value::Boolean(false)),
+            Self::Enabled { keep_going } => Some(table([
+                ("wait", Value::Boolean(true)),
+                ("keep-going", Value::Boolean(*keep_going)),
+            ])),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Wait {
+    type Error = anyhow::Error;
+    fn try_from(wait: &Value) -> anyhow::Result<Self> {
+        match wait {
+            Value::Boolean(false) => Ok(Self::Disabled),
+            Value::Boolean(true) => Ok(Self::Enabled { keep_going: false }),
+            Value::Table(table) => {
+                let enabled = table
+                    .get("wait")
+                    .map(try_into_boolean("config.wait.wait"))
+                    .transpose()?
+                    .unwrap_or(false);
+
+                let keep_going = table
+                    .get("keep-going")
+                    .map(try_into_boolean("config.wait.keep-going"))
+                    .transpose()?
+                    .unwrap_or(false);
+
+                if enabled {
+                    Ok(Self::Enabled { keep_going })
+                } else {
+                    Ok(Self::Disabled)
+                }
+            }
+            other => type_error("config.wait", &["boolean", "table"], other.type_str()),
+        }
+    }
+}
+
+/// Whether to run all selected commands at once, instead of waiting for each before the next.
+#[derive(Debug, Default, Clone)]
+pub struct Parallel(pub bool);
+
+impl ConfigItem for Parallel {
+    fn name() -> &'static str {
+        "parallel"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for Parallel {
+    type Error = anyhow::Error;
+    fn try_from(parallel: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.parallel")(parallel).map(Self)
+    }
+}
+
+/// Whether to start commands in their own process group, so they outlive whatever started `dmm`.
+#[derive(Debug, Default, Clone)]
+pub struct Detach(pub bool);
+
+impl ConfigItem for Detach {
+    fn name() -> &'static str {
+        "detach"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for Detach {
+    type Error = anyhow::Error;
+    fn try_from(detach: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.detach")(detach).map(Self)
+    }
+}
+
+/// Whether to send a desktop notification with `notify-send` when a command can't be run.
+#[derive(Debug, Default, Clone)]
+pub struct Notify(pub bool);
+
+impl ConfigItem for Notify {
+    fn name() -> &'static str {
+        "notify"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for Notify {
+    type Error = anyhow::Error;
+    fn try_from(notify: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.notify")(notify).map(Self)
+    }
+}
+
+/// The exit code to exit with if nothing is selected; `true` means 1.
+#[derive(Debug, Default, Clone)]
+pub struct ExitOnCancel(pub i32);
+
+impl ConfigItem for ExitOnCancel {
+    fn name() -> &'static str {
+        "exit-on-cancel"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Integer(self.0.into()))
+    }
+}
+
+impl TryFrom<&Value> for ExitOnCancel {
+    type Error = anyhow::Error;
+    fn try_from(exit: &Value) -> anyhow::Result<Self> {
+        match exit {
+            Value::Boolean(exit) => Ok(Self(i32::from(*exit))),
+            Value::Integer(code @ 0..=255) => Ok(Self(*code as i32)),
+            Value::Integer(code) => Err(anyhow!(
+                "`{}` must be an exit code from 0 to 255, but is {code}",
+                style_stderr!(bold(), "config.exit-on-cancel"),
+            )),
+            other => type_error(
+                "config.exit-on-cancel",
+                &["boolean", "integer"],
+                other.type_str(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub enum Source {
+    #[default]
+    Disabled,
+    Enabled {
+        source: Run,
+        run: Run,
+        group: i64,
+    },
+}
+
+impl ConfigItem for Source {
+    const KEYS: Option<&'static [&'static str]> = Some(&["source", "run", "group"]);
+
+    fn name() -> &'static str {
+        "source"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Self::Disabled => None,
+            Self::Enabled { source, run, group } => Some(table([
+                ("source", source.to_value()),
+                ("run", run.to_value()),
+                ("group", Value::Integer(*group)),
+            ])),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Source {
+    type Error = anyhow::Error;
+    fn try_from(source: &Value) -> anyhow::Result<Self> {
+        let source = try_into_table("config.source")(source)?;
+        let missing_error = |key: &str| {
+            format!(
+                "`{}` must have a value if `{}` is set",
+                style_stderr!(bold(), "config.source.{key}"),
+                style_stderr!(bold(), "config.source"),
+            )
+        };
+
+        let command = source
+            .get("source")
+            .map(try_into_run("config.source.source"))
+            .transpose()?
+            .with_context(|| missing_error("source"))?;
+
+        let run = source
+            .get("run")
+            .map(try_into_run("config.source.run"))
+            .transpose()?
+            .with_context(|| missing_error("run"))?;
+
+        let group = source
+            .get("group")
+            .map(try_into_integer("config.source.group"))
+            .transpose()?
+            .unwrap_or(0);
+
+        Ok(Self::Enabled {
+            source: command,
+            run,
+            group,
+        })
+    }
+}
+
+/// A command to run before showing the menu; if it fails, the menu isn't shown.
+#[derive(Debug, Default, Clone)]
+pub struct Pre(pub Option<Run>);
+
+impl ConfigItem for Pre {
+    fn name() -> &'static str {
+        "pre"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        self.0.as_ref().map(Run::to_value)
+    }
+}
+
+impl TryFrom<&Value> for Pre {
+    type Error = anyhow::Error;
+    fn try_from(pre: &Value) -> anyhow::Result<Self> {
+        try_into_run("config.pre")(pre).map(|pre| Self(Some(pre)))
+    }
+}
+
+/// A command to run after the selected commands, even if they failed.
+#[derive(Debug, Default, Clone)]
+pub struct Post(pub Option<Run>);
+
+impl ConfigItem for Post {
+    fn name() -> &'static str {
+        "post"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        self.0.as_ref().map(Run::to_value)
+    }
+}
+
+impl TryFrom<&Value> for Post {
+    type Error = anyhow::Error;
+    fn try_from(post: &Value) -> anyhow::Result<Self> {
+        try_into_run("config.post")(post).map(|post| Self(Some(post)))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub enum HistoryPath {
+    #[default]
+    Disabled,
+    /// Keep the history in the cache directory.
+    Default,
+    Path(ImStr),
+}
+
+impl ConfigItem for HistoryPath {
+    fn name() -> &'static str {
+        "history"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Self::Disabled => Some(Value::Boolean(false)),
+            Self::Default => Some(Value::Boolean(true)),
+            Self::Path(path) => Some(Value::from(path.as_str())),
+        }
+    }
+}
+
+impl TryFrom<&Value> for HistoryPath {
+    type Error = anyhow::Error;
+    fn try_from(history: &Value) -> anyhow::Result<Self> {
+        match history {
+            Value::Boolean(false) => Ok(Self::Disabled),
+            Value::Boolean(true) => Ok(Self::Default),
+            Value::String(path) => Ok(Self::Path(ImStr::from(path))),
+            other => type_error("config.history", &["boolean", "string"], other.type_str()),
+        }
+    }
+}
+
+/// The launchers to try, in order; see [`Launcher::find`].
+#[derive(Debug, Clone)]
+pub struct Launcher(pub Vec<ImStr>);
+
+impl Launcher {
+    /// Launchers that need a flag to act like dmenu, reading entries from stdin,
+    /// and the flag.
+    const LAUNCHERS: &'static [(&'static str, &'static str)] = &[
+        ("rofi", "-dmenu"),
+        ("wofi", "--dmenu"),
+        ("fuzzel", "--dmenu"),
+    ];
+
+    /// The flag `launcher` needs to act like dmenu, if it's known to need one.
+    ///
+    /// ```
+    /// use dmm::config::Launcher;
+    ///
+    /// assert_eq!(Launcher::mode_arg("/usr/bin/rofi"), Some("-dmenu"));
+    /// assert_eq!(Launcher::mode_arg("fuzzel"), Some("--dmenu"));
+    /// assert_eq!(Launcher::mode_arg("bemenu"), None);
+    /// ```
+    pub fn mode_arg(launcher: &str) -> Option<&'static str> {
+        let name = Path::new(launcher).file_name()?.to_str()?;
+        let (_, flag) = Self::LAUNCHERS.iter().find(|(known, _)| *known == name)?;
+        Some(flag)
+    }
+
+    /// Find the first launcher that's an executable file, or the name of one on `PATH`.
+    ///
+    /// A single launcher is used without looking for it, so running it reports if it's missing.
+    pub fn find(&self) -> anyhow::Result<ImStr> {
+        if let [launcher] = self.0.as_slice() {
+            return Ok(launcher.clone());
+        }
+
+        self.0
+            .iter()
+            .find(|launcher| is_program(launcher))
+            .cloned()
+            .ok_or_else(|| {
+                let tried = self
+                    .0
+                    .iter()
+                    .map(|launcher| format!("`{}`", style_stderr!(bold(), "{launcher}")))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                anyhow!(
+                    "none of the launchers in `{}` were found: {tried}",
+                    style_stderr!(bold(), "config.launcher")
+                )
+            })
+    }
+}
+
+impl ConfigItem for Launcher {
+    fn name() -> &'static str {
+        "launcher"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        match self.0.as_slice() {
+            [launcher] => Some(Value::from(launcher.as_str())),
+            launchers => Some(string_array(launchers)),
+        }
+    }
+}
+
+impl Default for Launcher {
+    fn default() -> Self {
+        Self(vec![ImStr::new("dmenu")])
+    }
+}
+
+impl TryFrom<&Value> for Launcher {
+    type Error = anyhow::Error;
+    fn try_from(launcher: &Value) -> anyhow::Result<Self> {
+        match launcher {
+            Value::String(launcher) => Ok(Self(vec![ImStr::from(launcher)])),
+            Value::Array(launchers) => {
+                let launchers = launchers
+                    .iter()
+                    .map(try_into_array_string("config.launcher"))
+                    .collect::<anyhow::Result<Vec<ImStr>>>()?;
+                if launchers.is_empty() {
+                    return Err(anyhow!(
+                        "`{}` must have at least one launcher",
+                        style_stderr!(bold(), "config.launcher")
+                    ));
+                }
+                Ok(Self(launchers))
+            }
+            other => type_error("config.launcher", &["string", "array"], other.type_str()),
+        }
+    }
+}
+
+/// Whether to show entry icons; by default, they're shown if the launcher is known to support them.
+#[derive(Debug, Default, Clone)]
+pub enum Icons {
+    #[default]
+    Auto,
+    Enabled,
+    Disabled,
+}
+
+impl Icons {
+    /// Launchers that support icons with the `\0icon\x1f<name>` metadata protocol.
+    const LAUNCHERS: &'static [&'static str] = &["rofi", "fuzzel"];
+
+    pub fn is_enabled(&self, launcher: &str) -> bool {
+        match self {
+            Self::Auto => Path::new(launcher)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| Self::LAUNCHERS.contains(&name)),
+            Self::Enabled => true,
+            Self::Disabled => false,
+        }
+    }
+}
+
+impl ConfigItem for Icons {
+    fn name() -> &'static str {
+        "icons"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Self::Auto => None,
+            Self::Enabled => Some(Value::Boolean(true)),
+            Self::Disabled => Some(Value::Boolean(false)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Icons {
+    type Error = anyhow::Error;
+    fn try_from(icons: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.icons")(icons).map(|icons| {
+            if icons {
+                Self::Enabled
+            } else {
+                Self::Disabled
+            }
+        })
+    }
+}
+
+/// Whether entry names contain pango markup, like `<b>bold</b>`, for launchers that show it.
+#[derive(Debug, Default, Clone)]
+pub struct Markup(pub bool);
+
+impl Markup {
+    /// Launchers that can show pango markup, and the flag that enables it.
+    const LAUNCHERS: &'static [(&'static str, &'static str)] =
+        &[("rofi", "-markup-rows"), ("wofi", "--allow-markup")];
+
+    /// The argument to show markup with `launcher`, or `None` if it's disabled or unsupported.
+    ///
+    /// ```
+    /// use dmm::config::Markup;
+    ///
+    /// assert_eq!(Markup(true).arg("/usr/bin/rofi"), Some("-markup-rows"));
+    /// assert_eq!(Markup(true).arg("dmenu"), None);
+    /// assert_eq!(Markup(false).arg("rofi"), None);
+    /// ```
+    pub fn arg(&self, launcher: &str) -> Option<&'static str> {
+        let name = Path::new(launcher).file_name()?.to_str()?;
+        let (_, flag) = Self::LAUNCHERS.iter().find(|(known, _)| *known == name)?;
+
+        self.0.then_some(*flag)
+    }
+
+    /// Remove the markup from `markup`, leaving the text it shows.
+    ///
+    /// Anything that isn't valid markup, like a `<` without a `>`, is left as it is.
+    ///
+    /// ```
+    /// use dmm::config::Markup;
+    ///
+    /// let markup = "<b>fire</b>fox &amp; <span color='red'>more</span> &#x263a;";
+    /// assert_eq!(Markup::strip(markup), "firefox & more \u{263a}");
+    /// assert_eq!(Markup::strip("a < b & c"), "a < b & c");
+    /// ```
+    pub fn strip(markup: &str) -> String {
+        let mut text = String::with_capacity(markup.len());
+        let mut rest = markup;
+
+        while let Some(start) = rest.find(['<', '&']) {
+            text.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let stripped = if rest.starts_with('<') {
+                rest.find('>').map(|end| (None, end))
+            } else {
+                rest.find(';').and_then(|end| {
+                    let entity = match &rest[1..end] {
+                        "amp" => '&',
+                        "lt" => '<',
+                        "gt" => '>',
+                        "quot" => '"',
+                        "apos" => '\'',
+                        code => {
+                            let code = match code.strip_prefix("#x") {
+                                Some(hex) => u32::from_str_radix(hex, 16),
+                                None => code.strip_prefix('#')?.parse(),
+                            };
+                            char::from_u32(code.ok()?)?
+                        }
+                    };
+                    Some((Some(entity), end))
+                })
+            };
+
+            match stripped {
+                Some((entity, end)) => {
+                    text.extend(entity);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    text.push_str(&rest[..1]);
+                    rest = &rest[1..];
+                }
+            }
+        }
+        text.push_str(rest);
+
+        text
+    }
+}
+
+impl ConfigItem for Markup {
+    fn name() -> &'static str {
+        "markup"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for Markup {
+    type Error = anyhow::Error;
+    fn try_from(markup: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.markup")(markup).map(Self)
+    }
+}
+
+/// How long to wait for the launcher to exit, in milliseconds, before killing it.
+#[derive(Debug, Default, Clone)]
+pub struct Timeout(pub Option<u64>);
+
+impl Timeout {
+    pub fn duration(&self) -> Option<Duration> {
+        self.0.map(Duration::from_millis)
+    }
+}
+
+impl ConfigItem for Timeout {
+    fn name() -> &'static str {
+        "timeout"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        self.0.map(unsigned_value)
+    }
+}
+
+impl TryFrom<&Value> for Timeout {
+    type Error = anyhow::Error;
+    fn try_from(timeout: &Value) -> anyhow::Result<Self> {
+        try_into_integer("config.timeout")(timeout)
+            .and_then(try_into_unsigned_integer("config.timeout"))
+            .map(|timeout| Self(Some(timeout)))
+    }
+}
+
+/// Whether to replace invalid utf-8 in the launcher's output, instead of failing.
+#[derive(Debug, Default, Clone)]
+pub struct LossyInput(pub bool);
+
+impl ConfigItem for LossyInput {
+    fn name() -> &'static str {
+        "lossy-input"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for LossyInput {
+    type Error = anyhow::Error;
+    fn try_from(lossy: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.lossy-input")(lossy).map(Self)
+    }
+}
+
+/// How the menu is given to the launcher.
+#[derive(Debug, Default, Clone, Copy)]
+pub enum InputMethod {
+    /// Written to the launcher's stdin through a pipe, while it runs.
+    #[default]
+    Pipe,
+    /// Written to a temporary file first, which is the launcher's stdin.
+    File,
+}
+
+impl InputMethod {
+    const VALUES: &'static [&'static str] = &["pipe", "file"];
+}
+
+impl ConfigItem for InputMethod {
+    fn name() -> &'static str {
+        "input-method"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        let input_method = match self {
+            Self::Pipe => "pipe",
+            Self::File => "file",
+        };
+        Some(Value::from(input_method))
+    }
+}
+
+impl TryFrom<&Value> for InputMethod {
+    type Error = anyhow::Error;
+    fn try_from(input_method: &Value) -> anyhow::Result<Self> {
+        let input_method = try_into_string("config.input-method")(input_method)?;
+        match input_method.as_str() {
+            "pipe" => Ok(Self::Pipe),
+            "file" => Ok(Self::File),
+            other => Err(anyhow!(
+                "`{}` can't be `{}`; valid values are: {}",
+                style_stderr!(bold(), "config.input-method"),
+                style_stderr!(bold(), "{other}"),
+                Self::VALUES
+                    .iter()
+                    .map(|value| format!("`{}`", style_stderr!(bold(), "{value}")))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// The launcher prompt, used unless `config.dmenu.prompt` is set.
+#[derive(Debug, Default, Clone)]
+pub struct Prompt(pub Option<ImStr>);
+
+impl ConfigItem for Prompt {
+    fn name() -> &'static str {
+        "prompt"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        self.0.as_deref().map(Value::from)
+    }
+}
+
+impl TryFrom<&Value> for Prompt {
+    type Error = anyhow::Error;
+    fn try_from(prompt: &Value) -> anyhow::Result<Self> {
+        try_into_string("config.prompt")(prompt).map(|prompt| Self(Some(prompt)))
+    }
+}
+
+/// Text to start the launcher's search with, if it supports that,
+/// or otherwise to move the entries that contain it to the top of the menu.
+#[derive(Debug, Default, Clone)]
+pub struct Select(pub Option<ImStr>);
+
+impl Select {
+    /// Launchers that can start with a search, and the flag to pass it with.
+    const LAUNCHERS: &'static [(&'static str, &'static str)] =
+        &[("rofi", "-filter"), ("wofi", "--search")];
+
+    /// The arguments to start `launcher` with the search, if it supports that.
+    ///
+    /// ```
+    /// use dmm::config::Select;
+    ///
+    /// let select = Select(Some("fire".into()));
+    /// assert_eq!(select.args("/usr/bin/rofi").unwrap(), ["-filter", "fire"]);
+    /// assert!(select.args("dmenu").is_none());
+    /// assert!(Select(None).args("rofi").is_none());
+    /// ```
+    pub fn args(&self, launcher: &str) -> Option<[Cow<'_, str>; 2]> {
+        let select = self.0.as_deref()?;
+        let name = Path::new(launcher).file_name()?.to_str()?;
+        let (_, flag) = Self::LAUNCHERS.iter().find(|(known, _)| *known == name)?;
+
+        Some([Cow::from(*flag), Cow::from(select)])
+    }
+}
+
+impl ConfigItem for Select {
+    fn name() -> &'static str {
+        "select"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        self.0.as_deref().map(Value::from)
+    }
+}
 
-                let replace = table
-                    .get("replace")
-                    .map(try_into_boolean("config.path.replace"))
-                    .transpose()?
-                    .unwrap_or(false);
+impl TryFrom<&Value> for Select {
+    type Error = anyhow::Error;
+    fn try_from(select: &Value) -> anyhow::Result<Self> {
+        try_into_string("config.select")(select).map(|select| Self(Some(select)))
+    }
+}
 
-                let recursive = table
-                    .get("recursive")
-                    .map(try_into_boolean("config.path.recursive"))
-                    .transpose()?
-                    .unwrap_or(false);
+/// How many entries to show at once, with entries to show the next and previous pages,
+/// for menus that have more than that.
+#[derive(Debug, Default, Clone)]
+pub struct PageSize(pub Option<u64>);
 
-                let group = table
-                    .get("group")
-                    .map(try_into_integer("config.path.group"))
-                    .transpose()?
-                    .unwrap_or(0);
+impl ConfigItem for PageSize {
+    fn name() -> &'static str {
+        "page-size"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        self.0.map(unsigned_value)
+    }
+}
 
-                Ok(Self::Enabled {
-                    path,
-                    env,
-                    replace,
-                    recursive,
-                    group,
-                })
-            }
-            other => type_error(
-                "config.numbered.separator",
-                &["boolean", "array", "table"],
-                other.type_str(),
-            ),
+impl TryFrom<&Value> for PageSize {
+    type Error = anyhow::Error;
+    fn try_from(page_size: &Value) -> anyhow::Result<Self> {
+        let page_size = try_into_integer("config.page-size")(page_size)
+            .and_then(try_into_unsigned_integer("config.page-size"))?;
+        if page_size == 0 {
+            return Err(anyhow!(
+                "`{}` must be at least 1",
+                style_stderr!(bold(), "config.page-size")
+            ));
         }
+        Ok(Self(Some(page_size)))
+    }
+}
+
+/// Whether the main menu shows the categories of its entries, each opening a submenu of
+/// the entries in it, instead of every entry under a header for its category.
+#[derive(Debug, Default, Clone)]
+pub struct GroupByCategory(pub bool);
+
+impl ConfigItem for GroupByCategory {
+    fn name() -> &'static str {
+        "group-by-category"
+    }
+    fn merge(self, _: Self) -> Self {
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Boolean(self.0))
+    }
+}
+
+impl TryFrom<&Value> for GroupByCategory {
+    type Error = anyhow::Error;
+    fn try_from(group: &Value) -> anyhow::Result<Self> {
+        try_into_boolean("config.group-by-category")(group).map(Self)
     }
 }
 
@@ -592,10 +3401,33 @@ pub struct Dmenu {
     pub fast: bool,
     pub monitor: Option<u64>,
     pub window_id: Option<ImStr>,
+    /// More arguments to pass to the launcher, after all the others from `config.dmenu`.
+    pub extra: Option<Vec<ImStr>>,
 }
 
 impl Dmenu {
-    pub fn args(&self) -> Vec<Cow<'_, str>> {
+    /// Whether `launcher` is dmenu, the only launcher `config.dmenu` is passed to.
+    pub fn is_for(launcher: &str) -> bool {
+        Path::new(launcher)
+            .file_name()
+            .and_then(|name| name.to_str())
+            == Some("dmenu")
+    }
+
+    /// The arguments for `launcher`, which are only passed to dmenu, since other launchers
+    /// don't share its flags; they're configured with `config.launcher-args` instead.
+    ///
+    /// ```
+    /// use dmm::config::Dmenu;
+    ///
+    /// let dmenu = Dmenu { lines: Some(3), ..Dmenu::default() };
+    /// assert_eq!(dmenu.args("/usr/bin/dmenu"), ["-i", "-l", "3"]);
+    /// assert!(dmenu.args("fuzzel").is_empty());
+    /// ```
+    pub fn args(&self, launcher: &str) -> Vec<Cow<'_, str>> {
+        if !Self::is_for(launcher) {
+            return Vec::new();
+        }
         let mut args = Vec::with_capacity(12);
 
         let options = [
@@ -619,12 +3451,34 @@ impl Dmenu {
                 args.extend([Cow::from(flag), option]);
             }
         }
+        args.extend(
+            self.extra
+                .iter()
+                .flatten()
+                .map(|arg| Cow::from(arg.as_str())),
+        );
 
         args
     }
 }
 
 impl ConfigItem for Dmenu {
+    const KEYS: Option<&'static [&'static str]> = Some(&[
+        "prompt",
+        "font",
+        "background",
+        "foreground",
+        "selected-background",
+        "selected-foreground",
+        "lines",
+        "bottom",
+        "case-sensitive",
+        "fast",
+        "monitor",
+        "window-id",
+        "extra",
+    ]);
+
     fn name() -> &'static str {
         "dmenu"
     }
@@ -642,8 +3496,41 @@ impl ConfigItem for Dmenu {
             fast: self.fast || default.fast,
             monitor: self.monitor.or(default.monitor),
             window_id: self.window_id.or(default.window_id),
+            extra: self.extra.or(default.extra),
         }
     }
+    fn to_value(&self) -> Option<Value> {
+        let strings = [
+            ("prompt", &self.prompt),
+            ("font", &self.font),
+            ("background", &self.background),
+            ("foreground", &self.foreground),
+            ("selected-background", &self.selected_background),
+            ("selected-foreground", &self.selected_foreground),
+            ("window-id", &self.window_id),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, Value::from(value.as_deref()?))));
+
+        let integers = [("lines", self.lines), ("monitor", self.monitor)]
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, unsigned_value(value?))));
+
+        let booleans = [
+            ("bottom", self.bottom),
+            ("case-sensitive", self.case_sensitive),
+            ("fast", self.fast),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key, Value::Boolean(value)));
+
+        let extra = self
+            .extra
+            .as_deref()
+            .map(|extra| ("extra", string_array(extra)));
+
+        Some(table(strings.chain(integers).chain(booleans).chain(extra)))
+    }
 }
 
 impl TryFrom<&Value> for Dmenu {
@@ -707,10 +3594,90 @@ impl TryFrom<&Value> for Dmenu {
                 .get("window-id")
                 .map(try_into_string("config.dmenu.window-id"))
                 .transpose()?,
+            extra: dmenu
+                .get("extra")
+                .map(try_into_args("config.dmenu.extra"))
+                .transpose()?,
         })
     }
 }
 
+/// Arguments to pass to the launcher, after those from `config.dmenu` for dmenu,
+/// by the launcher's name.
+///
+/// The arguments for `default` are used for launchers without any of their own.
+#[derive(Debug, Default, Clone)]
+pub struct LauncherArgs(pub Vec<(ImStr, Vec<ImStr>)>);
+
+impl LauncherArgs {
+    /// The arguments for `launcher`, or those for `default` if it has none of its own.
+    ///
+    /// ```
+    /// use dmm::config::LauncherArgs;
+    ///
+    /// let args = LauncherArgs(vec![
+    ///     ("rofi".into(), vec!["-theme".into(), "gruvbox".into()]),
+    ///     ("default".into(), vec!["-i".into()]),
+    /// ]);
+    /// assert_eq!(args.args("/usr/bin/rofi").collect::<Vec<_>>(), ["-theme", "gruvbox"]);
+    /// assert_eq!(args.args("dmenu").collect::<Vec<_>>(), ["-i"]);
+    /// assert_eq!(LauncherArgs::default().args("rofi").count(), 0);
+    /// ```
+    pub fn args(&self, launcher: &str) -> impl Iterator<Item = Cow<'_, str>> {
+        let name = Path::new(launcher)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(launcher);
+        let find = |name: &str| self.0.iter().find(|(known, _)| known.as_str() == name);
+
+        find(name)
+            .or_else(|| find("default"))
+            .into_iter()
+            .flat_map(|(_, args)| args.iter().map(|arg| Cow::from(arg.as_str())))
+    }
+}
+
+impl ConfigItem for LauncherArgs {
+    const KEYS: Option<&'static [&'static str]> = None;
+
+    fn name() -> &'static str {
+        "launcher-args"
+    }
+    fn merge(mut self, default: Self) -> Self {
+        let launchers = self
+            .0
+            .iter()
+            .map(|(launcher, _)| launcher.clone())
+            .collect::<HashSet<ImStr>>();
+        self.0.extend(
+            default
+                .0
+                .into_iter()
+                .filter(|(launcher, _)| !launchers.contains(launcher)),
+        );
+        self
+    }
+    fn to_value(&self) -> Option<Value> {
+        Some(table(self.0.iter().map(|(launcher, args)| {
+            (launcher.as_str(), string_array(args))
+        })))
+    }
+}
+
+impl TryFrom<&Value> for LauncherArgs {
+    type Error = anyhow::Error;
+    fn try_from(launcher_args: &Value) -> anyhow::Result<Self> {
+        try_into_table("config.launcher-args")(launcher_args)?
+            .iter()
+            .map(|(launcher, args)| {
+                let args = try_into_args(&format!("config.launcher-args.{launcher}"))(args)?;
+                Ok((ImStr::from(launcher), args))
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Self)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub args: ArgMatches,
@@ -718,28 +3685,252 @@ pub struct Config {
     pub base_dirs: BaseDirs,
     pub entries: Vec<Entry>,
     pub shell: Shell,
+    pub shell_args: ShellArgs,
     pub custom: Custom,
+    pub ad_hoc_shell: AdHocShell,
+    pub ad_hoc_prefix: AdHocPrefix,
+    pub confirm_ad_hoc: ConfirmAdHoc,
+    pub editable: Editable,
     pub numbered: Numbered,
+    pub tags: Tags,
+    pub sort: Sort,
+    pub tag_padding: TagPadding,
+    pub show_tags: ShowTags,
+    pub show_descriptions: ShowDescriptions,
+    pub case_insensitive: CaseInsensitive,
+    pub fuzzy: Fuzzy,
     pub path: BinPath,
+    pub env: Env,
+    pub expand_env: ExpandEnv,
+    pub terminal: Terminal,
+    pub terminal_command: TerminalCommand,
+    pub opener: Opener,
+    pub wait: Wait,
+    pub parallel: Parallel,
+    pub detach: Detach,
+    pub notify: Notify,
+    pub exec: Exec,
+    pub capture: Capture,
+    pub exit_on_cancel: ExitOnCancel,
+    pub source: Source,
+    pub pre: Pre,
+    pub post: Post,
+    pub history: HistoryPath,
+    pub launcher: Launcher,
+    pub icons: Icons,
+    pub markup: Markup,
+    pub timeout: Timeout,
+    pub lossy_input: LossyInput,
+    pub input_method: InputMethod,
+    pub prompt: Prompt,
+    pub select: Select,
+    pub page_size: PageSize,
+    pub group_by_category: GroupByCategory,
     pub dmenu: Dmenu,
+    pub launcher_args: LauncherArgs,
+    /// Problems found in the config, like duplicate commands, and unknown keys with `--check`.
+    pub problems: Vec<String>,
+    /// The pattern that was piped in, if any, to use again when reloading.
+    piped: Option<String>,
 }
 
 impl Config {
+    /// Read the config again, to pick up any changes to its files.
+    ///
+    /// A piped pattern can't be read again, so the same one is used.
+    pub fn reload(&self) -> anyhow::Result<Self> {
+        load(
+            self.args.clone(),
+            self.dirs.clone(),
+            self.base_dirs.clone(),
+            self.piped.clone(),
+        )
+    }
+
+    /// The config items after merging every layer, as they would be written in `config`.
+    pub fn items_to_value(&self) -> Value {
+        fn item<T: ConfigItem>(item: &T) -> Option<(&'static str, Value)> {
+            Some((T::name(), item.to_value()?))
+        }
+
+        table(
+            [
+                item(&self.shell),
+                item(&self.shell_args),
+                item(&self.custom),
+                item(&self.ad_hoc_shell),
+                item(&self.ad_hoc_prefix),
+                item(&self.confirm_ad_hoc),
+                item(&self.editable),
+                item(&self.numbered),
+                item(&self.tags),
+                item(&self.sort),
+                item(&self.tag_padding),
+                item(&self.show_tags),
+                item(&self.show_descriptions),
+                item(&self.case_insensitive),
+                item(&self.fuzzy),
+                item(&self.path),
+                item(&self.env),
+                item(&self.expand_env),
+                item(&self.terminal),
+                item(&self.terminal_command),
+                item(&self.opener),
+                item(&self.wait),
+                item(&self.parallel),
+                item(&self.detach),
+                item(&self.notify),
+                item(&self.exec),
+                item(&self.capture),
+                item(&self.exit_on_cancel),
+                item(&self.source),
+                item(&self.pre),
+                item(&self.post),
+                item(&self.history),
+                item(&self.launcher),
+                item(&self.icons),
+                item(&self.markup),
+                item(&self.timeout),
+                item(&self.lossy_input),
+                item(&self.input_method),
+                item(&self.prompt),
+                item(&self.select),
+                item(&self.page_size),
+                item(&self.group_by_category),
+                item(&self.dmenu),
+                item(&self.launcher_args),
+            ]
+            .into_iter()
+            .flatten(),
+        )
+    }
+
+    /// Make a config from `layers`, which are in order of precedence, highest first.
     pub fn try_new(
-        config: &Value,
-        home_config: Option<&Value>,
+        layers: &[Layer],
         args: ArgMatches,
         dirs: ProjectDirs,
         base_dirs: BaseDirs,
     ) -> anyhow::Result<Self> {
-        let config_path = dirs.config_dir().join("config.toml");
+        let mut unknown_keys = Vec::new();
+        let mut problems = Vec::<String>::new();
+        for layer in layers {
+            let found = check(&layer.value);
+            let locate = |problem| match &layer.name {
+                Some(name) => format!("{problem} in {name}"),
+                None => problem,
+            };
+            unknown_keys.extend(found.unknown_keys.into_iter().map(locate));
+            problems.extend(found.other.into_iter().map(locate));
+        }
+
+        // `--check` lists every problem instead, so any typo can be fixed at once.
+        if !args.get_flag("check") {
+            // Listed in order, since each context is shown before what it wraps.
+            let mut keys = unknown_keys.iter().rev();
+            if let Some(last) = keys.next() {
+                let err = keys.fold(anyhow!("{last}"), |err, key| err.context(key.clone()));
+                return Err(err).context("the config has unknown keys, which may be typos");
+            }
+        }
+        let problems = unknown_keys.into_iter().chain(problems).collect();
+
+        // Expanded before any other item is parsed, so every item sees the expanded strings.
+        let expand_env = try_get_config::<ExpandEnv>(layers)?;
+        let expanded;
+        let layers = match expand_env {
+            ExpandEnv::Disabled => layers,
+            ExpandEnv::Enabled { strict } => {
+                expanded = layers
+                    .iter()
+                    .map(|layer| {
+                        let value = ExpandEnv::expand(strict, "", &layer.value)
+                            .with_context(|| layer.error())?;
+                        Ok(Layer {
+                            value,
+                            ..layer.clone()
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<Layer>>>()?;
+                &expanded
+            }
+        };
+
+        let menu = select_menu(layers, args.get_one::<String>("menu").map(String::as_str))?;
+        let menu_key = menu
+            .as_ref()
+            .map_or_else(|| "menu".to_owned(), |name| format!("menus.{name}"));
+        // Parsed first, since entries with a `url` are run with it.
+        let opener = try_get_config::<Opener>(layers)?;
+        let entries = try_get_entries(layers, menu.as_deref(), &opener)?;
+        let show_tags = try_get_config::<ShowTags>(layers)?;
+        if !show_tags.0 {
+            if let Some(duplicate) = find_duplicate_name(&entries, &menu_key) {
+                let (first, second) = duplicate.indices;
+                return Err(anyhow!(
+                    "entries {first} and {second} of `{}` are both named `{}`",
+                    style_stderr!(bold(), "{}", duplicate.menu),
+                    style_stderr!(bold(), "{}", duplicate.name)
+                )
+                .context(format!(
+                    "entries can't share a name if `{}` is false",
+                    style_stderr!(bold(), "config.show-tags")
+                )));
+            }
+        }
+
+        let shell_args = try_get_config::<ShellArgs>(layers)?;
+        let shell = try_get_config::<Shell>(layers)?.with_args(&shell_args);
+        shell.validate()?;
+
         Ok(Self {
-            entries: try_get_entries(config, home_config, &config_path)?,
-            shell: try_get_config::<Shell>(config, home_config, &config_path)?,
-            custom: try_get_config::<Custom>(config, home_config, &config_path)?,
-            numbered: try_get_config::<Numbered>(config, home_config, &config_path)?,
-            path: try_get_config::<BinPath>(config, home_config, &config_path)?,
-            dmenu: try_get_config::<Dmenu>(config, home_config, &config_path)?,
+            entries,
+            show_tags,
+            shell,
+            shell_args,
+            show_descriptions: try_get_config::<ShowDescriptions>(layers)?,
+            custom: try_get_config::<Custom>(layers)?,
+            ad_hoc_shell: try_get_config::<AdHocShell>(layers)?,
+            ad_hoc_prefix: try_get_config::<AdHocPrefix>(layers)?,
+            confirm_ad_hoc: try_get_config::<ConfirmAdHoc>(layers)?,
+            editable: try_get_config::<Editable>(layers)?,
+            numbered: try_get_config::<Numbered>(layers)?,
+            tags: try_get_config::<Tags>(layers)?,
+            sort: try_get_config::<Sort>(layers)?,
+            tag_padding: try_get_config::<TagPadding>(layers)?,
+            case_insensitive: try_get_config::<CaseInsensitive>(layers)?,
+            fuzzy: try_get_config::<Fuzzy>(layers)?,
+            path: try_get_config::<BinPath>(layers)?,
+            env: try_get_config::<Env>(layers)?,
+            expand_env,
+            terminal: try_get_config::<Terminal>(layers)?,
+            terminal_command: try_get_config::<TerminalCommand>(layers)?,
+            opener,
+            wait: try_get_config::<Wait>(layers)?,
+            parallel: try_get_config::<Parallel>(layers)?,
+            detach: try_get_config::<Detach>(layers)?,
+            notify: try_get_config::<Notify>(layers)?,
+            exec: try_get_config::<Exec>(layers)?,
+            capture: try_get_config::<Capture>(layers)?,
+            exit_on_cancel: try_get_config::<ExitOnCancel>(layers)?,
+            source: try_get_config::<Source>(layers)?,
+            pre: try_get_config::<Pre>(layers)?,
+            post: try_get_config::<Post>(layers)?,
+            history: try_get_config::<HistoryPath>(layers)?,
+            launcher: try_get_config::<Launcher>(layers)?,
+            icons: try_get_config::<Icons>(layers)?,
+            markup: try_get_config::<Markup>(layers)?,
+            timeout: try_get_config::<Timeout>(layers)?,
+            lossy_input: try_get_config::<LossyInput>(layers)?,
+            input_method: try_get_config::<InputMethod>(layers)?,
+            prompt: try_get_config::<Prompt>(layers)?,
+            select: try_get_config::<Select>(layers)?,
+            page_size: try_get_config::<PageSize>(layers)?,
+            group_by_category: try_get_config::<GroupByCategory>(layers)?,
+            dmenu: try_get_config::<Dmenu>(layers)?,
+            launcher_args: try_get_config::<LauncherArgs>(layers)?,
+            problems,
+            piped: None,
             args,
             dirs,
             base_dirs,
@@ -747,74 +3938,405 @@ impl Config {
     }
 }
 
+/// Keys that are valid in a menu entry table.
+const ENTRY_KEYS: &[&str] = &[
+    "run",
+    "args",
+    "steps",
+    "ignore-errors",
+    "url",
+    "group",
+    "cwd",
+    "env",
+    "values",
+    "terminal",
+    "confirm",
+    "confirm-prompt",
+    "hidden",
+    "disabled",
+    "icon",
+    "description",
+    "key",
+    "category",
+    "aliases",
+    "when",
+    "check",
+    "schedule",
+    "sticky",
+    "submenu",
+    "prompt",
+];
+
+/// Problems found in a config.
+#[derive(Debug, Default)]
+struct Problems {
+    /// Keys that aren't known, which are likely typos, so they prevent the config from being used.
+    unknown_keys: Vec<String>,
+    /// Problems that don't prevent the config from being used.
+    other: Vec<String>,
+}
+
+/// Find problems in a config, like unknown keys in any of its tables.
+fn check(config: &Value) -> Problems {
+    let mut problems = Problems::default();
+    let config = match config {
+        Value::Table(config) => config,
+        _ => return problems,
+    };
+
+    check_keys(
+        config,
+        None,
+        &["menu", "menus", "config", "include"],
+        &mut problems,
+    );
+
+    if let Some(Value::Table(items)) = config.get("config") {
+        let item_keys = config_item_keys();
+        for (name, value) in items {
+            match item_keys.iter().find(|(item, _)| item == name) {
+                Some((_, Some(keys))) => {
+                    if let Value::Table(item) = value {
+                        check_keys(item, Some(&format!("config.{name}")), keys, &mut problems);
+                    }
+                }
+                Some((_, None)) => {}
+                None => problems
+                    .unknown_keys
+                    .push(unknown_key_problem(&format!("config.{name}"))),
+            }
+        }
+    }
+
+    if let Some(Value::Table(menu)) = config.get("menu") {
+        let entries = menu
+            .iter()
+            .map(|(name, value)| (name.as_str(), format!("menu.{name}"), value))
+            .collect();
+        check_menu(entries, false, &mut problems);
+    }
+
+    if let Some(Value::Table(menus)) = config.get("menus") {
+        for (menu, entries) in menus {
+            if let Value::Table(entries) = entries {
+                let entries = entries
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), format!("menus.{menu}.{name}"), value))
+                    .collect();
+                check_menu(entries, false, &mut problems);
+            }
+        }
+    }
+
+    problems
+}
+
+fn check_menu(entries: Vec<(&str, String, &Value)>, in_array: bool, problems: &mut Problems) {
+    let mut names = HashMap::<&str, String>::default();
+    let mut runs = HashMap::<String, String>::default();
+
+    for (name, key, value) in entries {
+        if let Some(first) = names.get(name) {
+            problems.other.push(format!(
+                "`{}` has the same name as `{}`",
+                style_stderr!(bold(), "{key}"),
+                style_stderr!(bold(), "{first}"),
+            ));
+        } else {
+            names.insert(name, key.clone());
+        }
+        if name.contains(['\n', tag::SEP]) {
+            problems.other.push(format!(
+                "`{}` has a newline or zero width non joiner in its name, \
+                 so it may not be found when it's selected",
+                style_stderr!(bold(), "{key}"),
+            ));
+        }
+
+        let run = match value {
+            Value::Table(entry) => entry
+                .get("run")
+                .or_else(|| entry.get("args"))
+                .or_else(|| entry.get("steps"))
+                .or_else(|| entry.get("url")),
+            run => Some(run),
+        };
+        if let Some(run @ (Value::String(_) | Value::Array(_))) = run {
+            // Compared as toml, since values can't be hashed.
+            match runs.get(&run.to_string()) {
+                Some(first) => problems.other.push(format!(
+                    "`{}` runs the same command as `{}`",
+                    style_stderr!(bold(), "{key}"),
+                    style_stderr!(bold(), "{first}"),
+                )),
+                None => {
+                    runs.insert(run.to_string(), key.clone());
+                }
+            }
+        }
+
+        let entry = match value {
+            Value::Table(entry) => entry,
+            _ => continue,
+        };
+
+        if in_array {
+            let keys = ENTRY_KEYS
+                .iter()
+                .copied()
+                .chain(["name"])
+                .collect::<Vec<&str>>();
+            check_keys(entry, Some(&key), &keys, problems);
+        } else {
+            check_keys(entry, Some(&key), ENTRY_KEYS, problems);
+        }
+
+        match entry.get("submenu") {
+            Some(Value::Table(submenu)) => {
+                let entries = submenu
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), format!("{key}.submenu.{name}"), value))
+                    .collect();
+                check_menu(entries, false, problems);
+            }
+            Some(Value::Array(submenu)) => {
+                let entries = submenu
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, value)| {
+                        let name = value.get("name")?.as_str()?;
+                        Some((name, format!("{key}.submenu[{i}]"), value))
+                    })
+                    .collect();
+                check_menu(entries, true, problems);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_keys(
+    table: &Map<String, Value>,
+    key: Option<&str>,
+    valid: &[&str],
+    problems: &mut Problems,
+) {
+    for name in table.keys() {
+        if !valid.contains(&name.as_str()) {
+            let name = match key {
+                Some(key) => format!("{key}.{name}"),
+                None => name.clone(),
+            };
+            problems.unknown_keys.push(unknown_key_problem(&name));
+        }
+    }
+}
+
+fn unknown_key_problem(key: &str) -> String {
+    format!("`{}` is not a known key", style_stderr!(bold(), "{key}"))
+}
+
+/// A name that is shared by more than one entry in the same menu or submenu.
+struct DuplicateName {
+    name: ImStr,
+    /// The dotted path to the menu the entries are in.
+    menu: String,
+    /// The indices of the first two entries with the name.
+    indices: (usize, usize),
+}
+
+/// Find a name that is shared by more than one entry in the same menu or submenu,
+/// which is at the dotted path `menu`.
+fn find_duplicate_name(entries: &[Entry], menu: &str) -> Option<DuplicateName> {
+    let mut names = HashMap::<ImStr, usize>::default();
+
+    for (i, entry) in entries.iter().enumerate() {
+        if let Entry::Filter(_) = entry {
+            continue;
+        }
+        if let Some(first) = names.insert(entry.name(), i) {
+            return Some(DuplicateName {
+                name: entry.name(),
+                menu: menu.to_owned(),
+                indices: (first, i),
+            });
+        }
+        if let Entry::Submenu { name, entries, .. } = entry {
+            if let Some(duplicate) = find_duplicate_name(entries, &format!("{menu}.{name}.submenu"))
+            {
+                return Some(duplicate);
+            }
+        }
+    }
+
+    None
+}
+
+/// Each item in `config`, with the keys that are valid if it is a table.
+fn config_item_keys() -> Vec<(&'static str, Option<&'static [&'static str]>)> {
+    fn item<T: ConfigItem>() -> (&'static str, Option<&'static [&'static str]>) {
+        (T::name(), T::KEYS)
+    }
+
+    vec![
+        item::<Shell>(),
+        item::<ShellArgs>(),
+        item::<Custom>(),
+        item::<AdHocShell>(),
+        item::<AdHocPrefix>(),
+        item::<ConfirmAdHoc>(),
+        item::<Editable>(),
+        item::<Numbered>(),
+        item::<Tags>(),
+        item::<Sort>(),
+        item::<TagPadding>(),
+        item::<ShowTags>(),
+        item::<ShowDescriptions>(),
+        item::<CaseInsensitive>(),
+        item::<Fuzzy>(),
+        item::<BinPath>(),
+        item::<Env>(),
+        item::<ExpandEnv>(),
+        item::<Terminal>(),
+        item::<TerminalCommand>(),
+        item::<Opener>(),
+        item::<Wait>(),
+        item::<Parallel>(),
+        item::<Detach>(),
+        item::<Notify>(),
+        item::<Exec>(),
+        item::<Capture>(),
+        item::<ExitOnCancel>(),
+        item::<Source>(),
+        item::<Pre>(),
+        item::<Post>(),
+        item::<HistoryPath>(),
+        item::<Launcher>(),
+        item::<Icons>(),
+        item::<Markup>(),
+        item::<Timeout>(),
+        item::<LossyInput>(),
+        item::<InputMethod>(),
+        item::<Prompt>(),
+        item::<Select>(),
+        item::<PageSize>(),
+        item::<GroupByCategory>(),
+        item::<Dmenu>(),
+        item::<LauncherArgs>(),
+    ]
+}
+
+/// Choose the menu in `menus` to show, or `None` to show `menu`.
+///
+/// Without a `name`, the menu named `default` is chosen, or the only menu if there's one.
+fn select_menu(layers: &[Layer], name: Option<&str>) -> anyhow::Result<Option<String>> {
+    let mut names = Vec::<String>::new();
+    for layer in layers {
+        let menus = layer
+            .value
+            .get("menus")
+            .map(try_into_table("menus"))
+            .transpose()
+            .context(layer.error())?;
+        for menu in menus.into_iter().flat_map(Map::keys) {
+            if !names.contains(menu) {
+                names.push(menu.clone());
+            }
+        }
+    }
+    let available = || {
+        names
+            .iter()
+            .map(|name| format!("`{}`", style_stderr!(bold(), "{name}")))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    match name {
+        Some(name) if names.iter().any(|menu| menu == name) => Ok(Some(name.to_owned())),
+        Some(name) if names.is_empty() => Err(anyhow!(
+            "there's no menu named `{}`; the config doesn't define `{}`",
+            style_stderr!(bold(), "{name}"),
+            style_stderr!(bold(), "menus")
+        )),
+        Some(name) => Err(anyhow!(
+            "there's no menu named `{}`; the available menus are {}",
+            style_stderr!(bold(), "{name}"),
+            available()
+        )),
+        None if names.is_empty() => Ok(None),
+        None if names.iter().any(|menu| menu == "default") => Ok(Some("default".to_owned())),
+        None if names.len() == 1 => Ok(names.pop()),
+        None => Err(anyhow!(
+            "choose a menu to show with `{}`; the available menus are {}",
+            style_stderr!(bold(), "--menu"),
+            available()
+        )),
+    }
+}
+
+/// Get the entries of `menus.<menu>`, or of `menu` if `menu` is `None`, from every layer.
 fn try_get_entries(
-    config: &Value,
-    home_config: Option<&Value>,
-    config_path: &Path,
+    layers: &[Layer],
+    menu: Option<&str>,
+    opener: &Opener,
 ) -> anyhow::Result<Vec<Entry>> {
-    let mut menu = config
-        .get("menu")
-        .map(try_into_table("menu"))
-        .transpose()?
-        .into_iter()
-        .flatten()
-        .map(|(name, value)| Entry::try_new(ImStr::from(name), value))
-        .collect::<Result<Vec<Entry>, _>>()
-        .context(target_config_error())?;
-
-    let home_menu = home_config
-        .and_then(|config| config.get("menu"))
-        .map(try_into_table("menu"))
-        .transpose()?
-        .into_iter()
-        .flatten()
-        .map(|(name, value)| Entry::try_new(ImStr::from(name), value))
-        .collect::<Result<Vec<Entry>, _>>()
-        .context(home_config_error(config_path))?;
-
-    let entry_names = menu.iter().map(Entry::name).collect::<HashSet<ImStr>>();
+    let key = menu.map_or_else(|| "menu".to_owned(), |menu| format!("menus.{menu}"));
+    let mut entries = Vec::new();
+    let mut entry_names = HashSet::default();
 
-    menu.extend(
-        home_menu
+    for layer in layers {
+        let table = match menu {
+            Some(menu) => layer.value.get("menus").and_then(|menus| menus.get(menu)),
+            None => layer.value.get("menu"),
+        };
+        let layer_entries = table
+            .map(try_into_table(&key))
+            .transpose()
+            .context(layer.error())?
             .into_iter()
-            .filter(|entry| !entry_names.contains(&entry.name())),
-    );
+            .flatten()
+            .filter(|(name, _)| !entry_names.contains(name.as_str()))
+            .map(|(name, value)| {
+                Entry::try_new(
+                    ImStr::from(name),
+                    &format!("{key}.{name}"),
+                    value,
+                    &layer.base,
+                    opener,
+                )
+            })
+            .collect::<Result<Vec<Entry>, _>>()
+            .context(layer.error())?;
+
+        entry_names.extend(layer_entries.iter().map(Entry::name));
+        entries.extend(layer_entries);
+    }
 
-    Ok(menu)
+    Ok(entries)
 }
 
-fn try_get_config<'a, T: ConfigItem>(
-    config: &'a Value,
-    home_config: Option<&'a Value>,
-    config_path: &Path,
-) -> anyhow::Result<T> {
-    let config = config
-        .get("config")
-        .map(try_into_table("config"))
-        .transpose()
-        .context(target_config_error())?
-        .and_then(|config| config.get(T::name()))
-        .map(T::try_from)
-        .transpose()
-        .context(target_config_error())?;
+fn try_get_config<T: ConfigItem>(layers: &[Layer]) -> anyhow::Result<T> {
+    let mut config = T::default();
 
-    let home_config = home_config
-        .and_then(|config| config.get("config"))
-        .map(try_into_table("config"))
-        .transpose()
-        .context(home_config_error(config_path))?
-        .and_then(|config| config.get(T::name()))
-        .map(T::try_from)
-        .transpose()
-        .context(home_config_error(config_path))?
-        .map(|config| config.merge(T::default()))
-        .unwrap_or_default();
+    for layer in layers.iter().rev() {
+        let item = layer
+            .value
+            .get("config")
+            .map(try_into_table("config"))
+            .transpose()
+            .context(layer.error())?
+            .and_then(|config| config.get(T::name()))
+            .map(T::try_from)
+            .transpose()
+            .context(layer.error())?;
 
-    if let Some(config) = config {
-        Ok(config.merge(home_config))
-    } else {
-        Ok(home_config)
+        if let Some(item) = item {
+            config = item.merge(config);
+        }
     }
+
+    Ok(config)
 }
 
 fn type_error<T>(name: &str, valid: &[&str], found: &str) -> anyhow::Result<T> {
@@ -893,6 +4415,70 @@ fn try_into_array_string(name: &str) -> impl Fn(&Value) -> anyhow::Result<ImStr>
     }
 }
 
+/// Parse arguments, which may be an array of strings, or a string split like a shell would.
+fn try_into_args(name: &str) -> impl Fn(&Value) -> anyhow::Result<Vec<ImStr>> + '_ {
+    move |value| match value {
+        Value::String(args) => template::split_words(args)
+            .map(|args| args.into_iter().map(ImStr::from).collect())
+            .context(format!("`{}` is invalid", style_stderr!(bold(), "{name}"))),
+        Value::Array(args) => args.iter().map(try_into_array_string(name)).collect(),
+        other => type_error(name, &["string", "array"], other.type_str()),
+    }
+}
+
+fn try_into_run(name: &str) -> impl Fn(&Value) -> anyhow::Result<Run> + '_ {
+    move |value| match value {
+        Value::String(run) => Ok(Run::Shell(ImStr::from(run))),
+        Value::Array(run) => run
+            .iter()
+            .map(try_into_array_string(name))
+            .collect::<Result<Vec<ImStr>, _>>()
+            .map(Run::Bare),
+        other => type_error(name, &["string", "array"], other.type_str()),
+    }
+}
+
+fn try_into_env(name: &str) -> impl Fn(&Value) -> anyhow::Result<Vec<(ImStr, ImStr)>> + '_ {
+    move |value| {
+        try_into_table(name)(value)?
+            .iter()
+            .map(|(key, value)| {
+                let value = try_into_string(&format!("{name}.{key}"))(value)?;
+                Ok((ImStr::from(key), value))
+            })
+            .collect()
+    }
+}
+
+fn try_into_schedules(name: &str) -> impl Fn(&Value) -> anyhow::Result<Vec<Schedule>> + '_ {
+    let parse = move |schedule: ImStr| {
+        Schedule::parse(&schedule).context(format!(
+            "problem with `{}`",
+            style_stderr!(bold(), "{name}")
+        ))
+    };
+    move |value| match value {
+        Value::String(schedule) => Ok(vec![parse(ImStr::from(schedule))?]),
+        Value::Array(schedules) => schedules
+            .iter()
+            .map(|schedule| parse(try_into_array_string(name)(schedule)?))
+            .collect(),
+        other => type_error(name, &["string", "array"], other.type_str()),
+    }
+}
+
+fn try_into_values(name: &str) -> impl Fn(&Value) -> anyhow::Result<Vec<(ImStr, Run)>> + '_ {
+    move |value| {
+        try_into_table(name)(value)?
+            .iter()
+            .map(|(key, value)| {
+                let run = try_into_run(&format!("{name}.{key}"))(value)?;
+                Ok((ImStr::from(key), run))
+            })
+            .collect()
+    }
+}
+
 fn try_into_unsigned_integer(name: &str) -> impl Fn(i64) -> anyhow::Result<u64> + '_ {
     move |value| {
         value.try_into().map_err(|_| {
@@ -904,18 +4490,48 @@ fn try_into_unsigned_integer(name: &str) -> impl Fn(i64) -> anyhow::Result<u64>
     }
 }
 
-fn home_config_error(path: &Path) -> String {
-    format!(
-        "found a problem with home config `{}`",
-        style_stderr!(bold(), "{}", path.display())
-    )
-}
-
 const fn target_config_error() -> &'static str {
     "found a problem with provided config"
 }
 
 trait ConfigItem: for<'a> TryFrom<&'a Value, Error = anyhow::Error> + Default {
+    /// The keys that are valid if the item is a table, or `None` if any key is valid.
+    const KEYS: Option<&'static [&'static str]> = Some(&[]);
+
     fn name() -> &'static str;
     fn merge(self, default: Self) -> Self;
+    /// The item as it would be written in a config, or `None` if it's unset.
+    fn to_value(&self) -> Option<Value>;
+}
+
+fn table<'a>(entries: impl IntoIterator<Item = (&'a str, Value)>) -> Value {
+    Value::Table(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect(),
+    )
+}
+
+fn string_array(strings: &[ImStr]) -> Value {
+    Value::Array(strings.iter().map(|s| Value::from(s.as_str())).collect())
+}
+
+fn env_table(env: &[(ImStr, ImStr)]) -> Value {
+    table(
+        env.iter()
+            .map(|(key, value)| (key.as_str(), Value::from(value.as_str()))),
+    )
+}
+
+fn values_table(values: &[(ImStr, Run)]) -> Value {
+    table(
+        values
+            .iter()
+            .map(|(key, run)| (key.as_str(), run.to_value())),
+    )
+}
+
+fn unsigned_value(int: u64) -> Value {
+    Value::Integer(i64::try_from(int).unwrap_or(i64::MAX))
 }
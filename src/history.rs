@@ -0,0 +1,93 @@
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use ahash::HashMap;
+use anyhow::Context;
+
+use crate::imstr::ImStr;
+use crate::style::{bold, style_stderr};
+
+/// How many times each menu entry has been selected, stored as lines of `count\tname`.
+///
+/// Malformed lines are ignored, so a corrupted history only loses the corrupted entries.
+///
+/// ```
+/// use dmm::history::History;
+///
+/// let mut history = History::parse("2\tfirefox\nnot a count\tbroken\n");
+/// history.record("firefox".into());
+/// history.record("htop".into());
+///
+/// assert_eq!(history.count("firefox"), 3);
+/// assert_eq!(history.count("broken"), 0);
+/// assert_eq!(history.to_string(), "3\tfirefox\n1\thtop\n");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct History {
+    counts: HashMap<ImStr, u64>,
+}
+
+impl History {
+    pub fn parse(history: &str) -> Self {
+        let counts = history
+            .lines()
+            .filter_map(|line| {
+                let (count, name) = line.split_once('\t')?;
+                let count = count.trim().parse().ok()?;
+                (!name.is_empty()).then(|| (ImStr::from(name), count))
+            })
+            .collect();
+
+        Self { counts }
+    }
+
+    /// Read the history at `path`; a missing file is an empty history.
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(history) => Ok(Self::parse(&history)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).context(format!(
+                "unable to read history file `{}`",
+                style_stderr!(bold(), "{}", path.display())
+            )),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let error = || {
+            format!(
+                "unable to write history file `{}`",
+                style_stderr!(bold(), "{}", path.display())
+            )
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(error)?;
+        }
+        fs::write(path, self.to_string()).with_context(error)
+    }
+
+    pub fn count(&self, name: &str) -> u64 {
+        self.counts.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn record(&mut self, name: ImStr) {
+        *self.counts.entry(name).or_insert(0) += 1;
+    }
+}
+
+impl Display for History {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut counts = self.counts.iter().collect::<Vec<_>>();
+        counts.sort_unstable_by(|(l_name, l_count), (r_name, r_count)| {
+            r_count.cmp(l_count).then_with(|| l_name.cmp(r_name))
+        });
+
+        for (name, count) in counts {
+            writeln!(f, "{count}\t{name}")?;
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,9 @@
 pub mod config;
+pub mod history;
 pub mod imstr;
+pub mod logger;
+pub mod menu;
+pub mod schedule;
 pub mod style;
 pub mod tag;
+pub mod template;
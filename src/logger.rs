@@ -0,0 +1,52 @@
+use std::io::Write;
+
+use anyhow::anyhow;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use termcolor::{Color, ColorSpec, StandardStream};
+
+use crate::style::{stderr_color_choice, write_style};
+
+/// Log to stderr, prefixing each message with its level like errors and warnings are.
+///
+/// `verbosity` is how many times `--verbose` was given:
+/// 0 logs nothing, 1 logs info, and 2 or more also logs debug messages.
+/// Fails if a logger was already set.
+pub fn init(verbosity: u8) -> anyhow::Result<()> {
+    log::set_max_level(match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        _ => LevelFilter::Debug,
+    });
+    log::set_logger(&Logger).map_err(|_| anyhow!("a logger was already set"))
+}
+
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let (name, color) = match record.level() {
+            Level::Error => ("error:", Color::Red),
+            Level::Warn => ("warning:", Color::Yellow),
+            Level::Info => ("info:", Color::Green),
+            Level::Debug => ("debug:", Color::Blue),
+            Level::Trace => ("trace:", Color::Magenta),
+        };
+        let mut stderr = StandardStream::stderr(stderr_color_choice());
+        write_style!(
+            stderr,
+            ColorSpec::new().set_fg(Some(color)).set_bold(true),
+            "{name} "
+        );
+        let _ = writeln!(stderr, "{}", record.args());
+    }
+
+    fn flush(&self) {}
+}
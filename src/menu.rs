@@ -0,0 +1,2275 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::fs::ReadDir;
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use std::{env, fs, panic, process};
+
+use ahash::HashMap;
+use anyhow::{anyhow, Context};
+use is_executable::IsExecutable;
+use log::{debug, info};
+use termcolor::{Color, ColorSpec, StandardStream};
+use toml::{map::Map, Value};
+
+use crate::config::{
+    BinPath, Config, Custom, Dmenu, Entry, EntryOptions, ExpandEnv, HistoryPath, InputMethod,
+    Launcher, Markup, Run, Select, Shell, Sort, Source, Tags, Wait,
+};
+use crate::history::History;
+use crate::imstr::ImStr;
+use crate::schedule::LocalTime;
+use crate::style::{bold, stderr_color_choice, style_stderr, style_stdout, write_style};
+use crate::tag::{self, Alpha, Binary, Decimal, Hex, Tag};
+use crate::template::{expand_env, render, split_words};
+
+/// A menu entry that is ready to show in the menu.
+#[derive(Debug, Clone)]
+pub struct RunEntry {
+    pub name: ImStr,
+    pub action: Action,
+    pub options: EntryOptions,
+    /// Whether placeholders like `{name}` are substituted in the entry's command.
+    pub templated: bool,
+}
+
+/// What happens when a menu entry is selected.
+#[derive(Debug, Clone)]
+pub enum Action {
+    Run(Run),
+    Submenu {
+        entries: Rc<[Entry]>,
+        prompt: Option<ImStr>,
+    },
+    /// Show the page of the menu that starts at this index, from `config.page-size`.
+    Page(usize),
+    /// Show the entries in a category, from `config.group-by-category`.
+    Category(Rc<[RunEntry]>),
+}
+
+impl RunEntry {
+    /// Returns `None` if the entry filters out a name or is hidden.
+    pub fn try_from(entry: Entry, shell_is_enabled: bool) -> Option<Self> {
+        Self::with_hidden(entry, shell_is_enabled).filter(|entry| !entry.options.hidden)
+    }
+
+    /// Like [`RunEntry::try_from`], but keeps hidden entries.
+    pub fn with_hidden(entry: Entry, shell_is_enabled: bool) -> Option<Self> {
+        match entry {
+            Entry::Full { name, run, options } => Some(Self {
+                name,
+                action: Action::Run(run),
+                options,
+                templated: true,
+            }),
+            Entry::Submenu {
+                name,
+                entries,
+                prompt,
+                options,
+            } => Some(Self {
+                name,
+                action: Action::Submenu {
+                    entries: entries.into(),
+                    prompt,
+                },
+                options,
+                templated: false,
+            }),
+            Entry::Name(name) => Some(Self {
+                action: Action::Run(if shell_is_enabled {
+                    Run::Shell(name.clone())
+                } else {
+                    Run::binary(name.clone())
+                }),
+                name,
+                options: EntryOptions::default(),
+                templated: false,
+            }),
+            Entry::Filter(_) => None,
+        }
+    }
+}
+
+/// A selected command that is ready to run.
+#[derive(Debug, Clone)]
+pub struct Choice {
+    pub name: ImStr,
+    pub run: Run,
+    pub options: EntryOptions,
+    /// The index of the selected entry in its menu, or `None` for ad-hoc commands.
+    pub id: Option<usize>,
+    /// The selected entry's tag as shown in the menu, or `None` for ad-hoc commands
+    /// and if tags aren't shown.
+    pub tag: Option<ImStr>,
+    /// How many entries were in the menu it was selected from.
+    pub count: usize,
+}
+
+impl Choice {
+    /// Make a choice from a selected entry, substituting any placeholders in its command.
+    ///
+    /// Placeholders named in the entry's `values` are replaced with their command's output.
+    /// Others, besides `{name}` and `{tag}`, are prompted for with the launcher,
+    /// in order of appearance; returns `None` if a prompt is cancelled.
+    fn try_new<T: Tag>(
+        entry: &RunEntry,
+        run: &Run,
+        id: usize,
+        count: usize,
+        config: &Config,
+    ) -> anyhow::Result<Option<Self>> {
+        // Without `config.numbered`, tags are invisible, so they'd be no use to a command,
+        // and `{tag}` is the entry's index instead.
+        let shows_tag = config.numbered.is_enabled() && config.show_tags.0;
+        let mut tag = String::new();
+        if shows_tag && config.tag_padding.0 {
+            T::push_padded_tag(id, count, &mut tag);
+        } else if shows_tag {
+            T::push_tag(id, &mut tag);
+        }
+        let tag = if shows_tag {
+            ImStr::from(tag.trim_matches(tag::SEP))
+        } else {
+            ImStr::from(id.to_string())
+        };
+
+        let run = if entry.templated {
+            let mut answers = HashMap::<String, ImStr>::default();
+            let mut cancelled = false;
+
+            let run = run.render(|placeholder| match placeholder {
+                "name" => Ok(entry.name.clone()),
+                "tag" => Ok(tag.clone()),
+                other => {
+                    if let Some(answer) = answers.get(other) {
+                        return Ok(answer.clone());
+                    }
+                    let value = entry
+                        .options
+                        .values
+                        .iter()
+                        .find(|(name, _)| name.as_str() == other);
+                    if let Some((_, value)) = value {
+                        let output = command_output(value, "value", config).context(format!(
+                            "unable to get the value of `{}`",
+                            style_stderr!(bold(), "{{{other}}}")
+                        ))?;
+                        let answer = ImStr::from(output.trim());
+                        answers.insert(other.to_owned(), answer.clone());
+                        return Ok(answer);
+                    }
+                    match prompt_input(other, config)? {
+                        Some(answer) => {
+                            answers.insert(other.to_owned(), answer.clone());
+                            Ok(answer)
+                        }
+                        None => {
+                            cancelled = true;
+                            Err(anyhow!("prompt for `{other}` was cancelled"))
+                        }
+                    }
+                }
+            });
+
+            match run {
+                Ok(run) => run,
+                Err(_) if cancelled => return Ok(None),
+                Err(err) => return Err(err),
+            }
+        } else {
+            run.clone()
+        };
+
+        Ok(Some(Self {
+            name: entry.name.clone(),
+            run,
+            options: entry.options.clone(),
+            id: Some(id),
+            tag: shows_tag.then_some(tag),
+            count,
+        }))
+    }
+}
+
+/// Use the launcher as a text input to get a value for `placeholder`.
+///
+/// Returns `None` if nothing was entered.
+fn prompt_input(placeholder: &str, config: &Config) -> anyhow::Result<Option<ImStr>> {
+    let launcher = &config.launcher.find()?;
+    let mut args = config.dmenu.args(launcher);
+    args.extend(config.launcher_args.args(launcher));
+    args.extend(cli_launcher_args(config));
+    args.extend([Cow::from("-p"), Cow::from(placeholder)]);
+
+    let input = run_launcher(launcher, |_| Ok(()), &args, config).context(format!(
+        "problem running launcher `{}` to prompt for `{}`",
+        style_stderr!(bold(), "{launcher}"),
+        style_stderr!(bold(), "{{{placeholder}}}")
+    ))?;
+    let input = input.trim_end_matches('\n');
+
+    Ok((!input.is_empty()).then(|| ImStr::from(input)))
+}
+
+/// Keep showing the menu and running the selected commands until nothing is selected,
+/// reading the config again before showing the menu each time.
+///
+/// Cancelling is how watching is stopped, so it exits with 0 despite `config.exit-on-cancel`.
+pub fn watch(mut config: Config) -> anyhow::Result<i32> {
+    loop {
+        if select_and_execute(&config)?.is_none() {
+            return Ok(0);
+        }
+
+        match config.reload() {
+            Ok(reloaded) => config = reloaded,
+            Err(err) => display_error(&err.context("unable to reload config; using the last one")),
+        }
+    }
+}
+
+/// Show the menu and run the selected commands, between `config.pre` and `config.post`,
+/// returning the exit code `dmm` should exit with, or `None` if nothing was selected.
+///
+/// The menu is shown again after running a `sticky` entry, until it's cancelled.
+pub fn select_and_execute(config: &Config) -> anyhow::Result<Option<i32>> {
+    if let Some(pre) = &config.pre.0 {
+        run_hook(pre, "pre", config)?;
+    }
+
+    let result = loop {
+        let commands = match select(config) {
+            Ok(commands) if commands.is_empty() => break Ok(None),
+            Ok(commands) => commands,
+            Err(err) => break Err(err),
+        };
+        let result = execute(&commands, config);
+        let sticky = commands.iter().any(|command| command.options.sticky);
+        if result.is_err() || !sticky || prints_commands(config) {
+            break result.map(Some);
+        }
+    };
+
+    if let Some(post) = &config.post.0 {
+        match run_hook(post, "post", config) {
+            // Don't hide why the selection failed behind the hook's failure.
+            Err(err) if result.is_err() => display_error(&err),
+            hook => hook?,
+        }
+    }
+
+    result
+}
+
+/// Run `config.pre` or `config.post`, waiting for it and failing if it fails.
+fn run_hook(hook: &Run, name: &str, config: &Config) -> anyhow::Result<()> {
+    let error = || {
+        format!(
+            "problem running `{}` command `{}`",
+            style_stderr!(bold(), "config.{name}"),
+            style_stderr!(bold(), "{hook}")
+        )
+    };
+
+    let (argv, input) = command_argv(hook, config).with_context(error)?;
+    let (program, args) = match argv.split_first() {
+        Some(argv) => argv,
+        None => return Ok(()),
+    };
+
+    let mut command = Command::new(program);
+    command.args(args);
+    if input.is_some() {
+        command.stdin(Stdio::piped());
+    }
+    for (key, value) in &config.env.0 {
+        command.env(key.as_str(), env_value(value, config));
+    }
+
+    info!("running `config.{name}` {command:?}");
+    let mut child = command
+        .spawn()
+        .context(format!(
+            "failed to run command `{}` (is it installed?)",
+            style_stderr!(bold(), "{program}")
+        ))
+        .with_context(error)?;
+
+    if let Some(input) = input {
+        child
+            .stdin
+            .take()
+            .context("failed to establish pipe to shell??")?
+            .write_all(input.as_bytes())
+            .context("failed to write to shell stdin??")?;
+    }
+
+    let status = child.wait().context("failed to wait for command??")?;
+    if !status.success() {
+        return Err(anyhow!("the command failed ({status})")).with_context(error);
+    }
+
+    Ok(())
+}
+
+pub fn select(config: &Config) -> anyhow::Result<Vec<Choice>> {
+    match (config.numbered.is_enabled(), config.tags) {
+        (true, Tags::Decimal) => get_selection::<Decimal>(config),
+        (true, Tags::Alpha) => get_selection::<Alpha>(config),
+        (true, Tags::Hex) => get_selection::<Hex>(config),
+        (false, _) => get_selection::<Binary>(config),
+    }
+}
+
+/// Whether the selected commands are printed instead of run.
+fn prints_commands(config: &Config) -> bool {
+    config.args.get_flag("dry-run")
+        || config.args.get_flag("print")
+        || config.args.contains_id("output")
+}
+
+/// Run the selected commands, or print them if `--dry-run`, `--print`, or `--output` was given.
+pub fn execute(commands: &[Choice], config: &Config) -> anyhow::Result<i32> {
+    if config.args.get_flag("dry-run") {
+        for command in commands {
+            println!(
+                "{}: {}",
+                style_stdout!(bold(), "{}", command.name),
+                command.run
+            );
+        }
+        return Ok(0);
+    }
+
+    if config.args.get_flag("print") {
+        for command in commands {
+            println!("{}", command.run);
+        }
+        return Ok(0);
+    }
+
+    if config.args.contains_id("output") {
+        for command in commands {
+            let null = || String::from("null");
+            let quote = |string: &str| serde_json::Value::from(string).to_string();
+            println!(
+                r#"{{"tag": {}, "index": {}, "name": {}, "command": {}}}"#,
+                command.tag.as_deref().map_or_else(null, quote),
+                command.id.map_or_else(null, |id| id.to_string()),
+                quote(&command.name),
+                quote(&command.run.to_string()),
+            );
+        }
+        return Ok(0);
+    }
+
+    run_commands(commands, config)
+}
+
+/// Make a pattern of the resolved config, with every menu entry, including hidden ones
+/// and those that `when` or `schedule` would leave out, since they aren't checked.
+///
+/// Entries that share a name with an earlier one, as a source's output may, are left out.
+pub fn dump_config(config: &Config) -> anyhow::Result<String> {
+    let history = History::default();
+    let mut entries = resolve_entries(config, true)?;
+    sort_entries(&mut entries, &history, config.sort);
+    let menu = menu_value(config, &entries, &history);
+
+    let mut pattern = Map::new();
+    pattern.insert("menu".to_owned(), menu);
+    pattern.insert("config".to_owned(), config.items_to_value());
+
+    toml::to_string_pretty(&Value::Table(pattern)).context("unable to write config as toml??")
+}
+
+fn menu_value(config: &Config, entries: &[RunEntry], history: &History) -> Value {
+    let mut menu = Map::new();
+    for entry in entries {
+        let mut table = Map::new();
+        match &entry.action {
+            Action::Run(run @ Run::Shell(_)) => {
+                table.insert("run".to_owned(), run.to_value());
+            }
+            Action::Run(run @ Run::Bare(_)) => {
+                table.insert("args".to_owned(), run.to_value());
+            }
+            Action::Run(run @ Run::Steps { ignore_errors, .. }) => {
+                table.insert("steps".to_owned(), run.to_value());
+                if *ignore_errors {
+                    table.insert("ignore-errors".to_owned(), Value::Boolean(true));
+                }
+            }
+            Action::Submenu { entries, prompt } => {
+                let mut entries = entries
+                    .iter()
+                    .filter_map(|entry| {
+                        RunEntry::with_hidden(entry.clone(), !config.shell.is_enabled())
+                    })
+                    .collect::<Vec<RunEntry>>();
+                sort_entries(&mut entries, history, config.sort);
+                table.insert("submenu".to_owned(), menu_value(config, &entries, history));
+                if let Some(prompt) = prompt {
+                    table.insert("prompt".to_owned(), Value::from(prompt.as_str()));
+                }
+            }
+            // Only added while the menu is shown, so they aren't part of the config.
+            Action::Page(_) | Action::Category(_) => continue,
+        }
+        entry.options.write_to(&mut table);
+
+        menu.entry(entry.name.to_string())
+            .or_insert(Value::Table(table));
+    }
+
+    Value::Table(menu)
+}
+
+/// Show the menu, and any submenus that are selected, returning all commands that were chosen.
+///
+/// If nothing is selected from a menu, the whole selection is cancelled.
+pub fn get_selection<T: Tag>(config: &Config) -> anyhow::Result<Vec<Choice>> {
+    let history_path = history_path(config);
+    let mut history = history_path
+        .as_deref()
+        .map(|path| {
+            History::read(path).unwrap_or_else(|err| {
+                warn_error(&err);
+                History::default()
+            })
+        })
+        .unwrap_or_default();
+
+    let launcher = &config.launcher.find()?;
+    // Only the main menu starts with `config.select`, which the launcher may not support.
+    let mut select = config.select.args(launcher);
+    let mut entries = build_entries(config, &history)?;
+    if let (Some(text), None) = (&config.select.0, &select) {
+        select_entries(&mut entries, text);
+    }
+    if config.group_by_category.0 {
+        entries = group_by_category(entries);
+    }
+
+    let markup = config.markup.arg(launcher);
+    let mut menus = vec![(entries, None, 0)];
+    let mut commands = Vec::new();
+
+    while let Some((menu, prompt, offset)) = menus.pop() {
+        // The whole menu is kept to show its other pages, if it has more than one.
+        let (mut entries, paged) = match page_size(&menu, config) {
+            Some(size) => (page(&menu, offset, size), Some(menu)),
+            None => (menu, None),
+        };
+        let mut args = menu_args(launcher, config, prompt.as_ref());
+        args.extend(select.take().into_iter().flatten());
+        args.extend(markup.map(Cow::from));
+        // Launchers that can't show markup would show it as text.
+        if config.markup.0 && markup.is_none() {
+            for entry in &mut entries {
+                entry.name = ImStr::from(Markup::strip(&entry.name));
+            }
+        }
+        let choices = run_launcher(
+            launcher,
+            |stdin| write_entries::<T>(config, &entries, stdin),
+            &args,
+            config,
+        )
+        .context(format!(
+            "problem running launcher `{}`",
+            style_stderr!(bold(), "{launcher}")
+        ))?;
+        let choices = choices
+            .split('\n')
+            .filter(|choice| !choice.trim().is_empty())
+            .collect::<Vec<&str>>();
+
+        if choices.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        for choice in choices {
+            if let Some(id) = find_entry::<T>(choice, &entries, config) {
+                let entry = match entries.get(id) {
+                    Some(entry) => entry,
+                    None => {
+                        let err = anyhow!(
+                            "the selection's tag is for entry {id}, \
+                             which is past the end of the menu"
+                        )
+                        .context(format!("can't run `{}`", style_stderr!(bold(), "{choice}")));
+                        warn_error(&err);
+                        continue;
+                    }
+                };
+
+                if entry.options.disabled {
+                    warn_error(&anyhow!(
+                        "`{}` is disabled",
+                        style_stderr!(bold(), "{}", entry.name)
+                    ));
+                    continue;
+                }
+
+                match &entry.action {
+                    Action::Submenu { entries, prompt } => {
+                        menus.push((build_submenu(config, entries, &history), prompt.clone(), 0));
+                    }
+                    Action::Page(offset) => {
+                        if let Some(menu) = &paged {
+                            menus.push((menu.clone(), prompt.clone(), *offset));
+                        }
+                    }
+                    Action::Category(entries) => {
+                        menus.push((entries.to_vec(), Some(entry.name.clone()), 0));
+                    }
+                    Action::Run(run) => {
+                        match Choice::try_new::<T>(entry, run, id, entries.len(), config) {
+                            Ok(Some(choice)) => commands.push(choice),
+                            Ok(None) => {}
+                            Err(err) => warn_error(&err.context(format!(
+                                "can't run `{}`",
+                                style_stderr!(bold(), "{}", entry.name)
+                            ))),
+                        }
+                    }
+                }
+            } else if is_category_header(choice, &entries) {
+                continue;
+            } else if let Custom::Enabled = config.custom {
+                let Some(command) = config.ad_hoc_prefix.strip(choice) else {
+                    let err = anyhow!(
+                        "ad-hoc commands must start with `{}`",
+                        style_stderr!(
+                            bold(),
+                            "{}",
+                            config.ad_hoc_prefix.0.as_deref().unwrap_or_default()
+                        )
+                    )
+                    .context(format!("can't run `{}`", style_stderr!(bold(), "{choice}")));
+                    warn_error(&err);
+                    continue;
+                };
+                let run = match typed_run(command, config) {
+                    Ok(run) => run,
+                    Err(err) => {
+                        warn_error(&err.context(format!(
+                            "can't run `{}`",
+                            style_stderr!(bold(), "{command}")
+                        )));
+                        continue;
+                    }
+                };
+                commands.push(Choice {
+                    name: command.into(),
+                    run,
+                    options: EntryOptions {
+                        confirm: config.confirm_ad_hoc.0,
+                        ..EntryOptions::default()
+                    },
+                    id: None,
+                    tag: None,
+                    count: entries.len(),
+                });
+            } else {
+                let err = anyhow!(
+                    "ad-hoc commands are disabled; consider setting `config.custom = true`"
+                )
+                .context(format!(
+                    "can't run `{}`",
+                    style_stderr!(&bold(), "{choice}")
+                ));
+
+                warn_error(&err);
+            }
+        }
+    }
+
+    if let Some(path) = history_path.filter(|_| !config.args.get_flag("dry-run")) {
+        for choice in commands.iter().filter(|choice| choice.id.is_some()) {
+            history.record(choice.name.clone());
+        }
+        if let Err(err) = history.write(&path) {
+            warn_error(&err);
+        }
+    }
+
+    Ok(commands)
+}
+
+/// The names of the entries that show the next and previous pages of a menu.
+const NEXT_PAGE: &str = "Next →";
+const PREVIOUS_PAGE: &str = "← Prev";
+
+/// The number of entries on each page of `menu`, or `None` if it fits on one page.
+fn page_size(menu: &[RunEntry], config: &Config) -> Option<usize> {
+    let size = usize::try_from(config.page_size.0?).unwrap_or(usize::MAX);
+    (menu.len() > size).then_some(size)
+}
+
+/// The `size` entries of `menu` from `offset`, after an entry to show the previous page
+/// and before one to show the next, if there are any.
+///
+/// Like a submenu, each page is tagged on its own, so tags are only for the page they're on.
+fn page(menu: &[RunEntry], offset: usize, size: usize) -> Vec<RunEntry> {
+    let nav = |name: &'static str, offset: usize| RunEntry {
+        name: ImStr::new(name),
+        action: Action::Page(offset),
+        options: EntryOptions::default(),
+        templated: false,
+    };
+
+    let end = menu.len().min(offset + size);
+    let mut page = Vec::with_capacity(end - offset + 2);
+    if offset > 0 {
+        page.push(nav(PREVIOUS_PAGE, offset.saturating_sub(size)));
+    }
+    page.extend_from_slice(&menu[offset..end]);
+    if end < menu.len() {
+        page.push(nav(NEXT_PAGE, end));
+    }
+
+    page
+}
+
+/// The name of the category that has the entries without one, from `config.group-by-category`.
+const DEFAULT_CATEGORY: &str = "Other";
+
+/// An entry for each category in `entries`, in the order of their first entries,
+/// that shows the entries in it without their category headers.
+///
+/// Entries without a category are grouped last, under [`DEFAULT_CATEGORY`];
+/// if no entry has a category, `entries` is returned as it is.
+fn group_by_category(entries: Vec<RunEntry>) -> Vec<RunEntry> {
+    if entries.iter().all(|entry| entry.options.category.is_none()) {
+        return entries;
+    }
+
+    let mut categories = Vec::<(ImStr, Vec<RunEntry>)>::new();
+    let mut uncategorized = Vec::new();
+    for mut entry in entries {
+        let Some(category) = entry.options.category.take() else {
+            uncategorized.push(entry);
+            continue;
+        };
+        match categories.iter_mut().find(|(name, _)| *name == category) {
+            Some((_, entries)) => entries.push(entry),
+            None => categories.push((category, vec![entry])),
+        }
+    }
+    if !uncategorized.is_empty() {
+        categories.push((ImStr::new(DEFAULT_CATEGORY), uncategorized));
+    }
+
+    categories
+        .into_iter()
+        .map(|(name, entries)| RunEntry {
+            name,
+            action: Action::Category(entries.into()),
+            options: EntryOptions::default(),
+            templated: false,
+        })
+        .collect()
+}
+
+/// The command to run for text typed into the launcher, in the shell unless
+/// `config.ad-hoc-shell` is false.
+fn typed_run(typed: &str, config: &Config) -> anyhow::Result<Run> {
+    if config.ad_hoc_shell.0 {
+        Ok(Run::Shell(typed.into()))
+    } else {
+        let words = split_words(typed)?;
+        Ok(Run::Bare(words.into_iter().map(ImStr::from).collect()))
+    }
+}
+
+/// Move the entries whose names contain `text`, ignoring case, to the top of the menu,
+/// for launchers that can't start with a search; the order is otherwise kept.
+fn select_entries(entries: &mut [RunEntry], text: &str) {
+    let text = text.to_lowercase();
+    entries.sort_by_key(|entry| !entry.name.to_lowercase().contains(&text));
+}
+
+/// Get the arguments to run the launcher with for a menu.
+///
+/// `prompt` is the menu's own prompt, which takes precedence over any other;
+/// `config.prompt` is only used if `config.dmenu.prompt` isn't set.
+fn menu_args<'a>(
+    launcher: &str,
+    config: &'a Config,
+    prompt: Option<&'a ImStr>,
+) -> Vec<Cow<'a, str>> {
+    let mut args = config.dmenu.args(launcher);
+    args.extend(config.launcher_args.args(launcher));
+    let prompt = prompt.or_else(|| {
+        config
+            .prompt
+            .0
+            .as_ref()
+            .filter(|_| config.dmenu.prompt.is_none() || !Dmenu::is_for(launcher))
+    });
+    if let Some(prompt) = prompt {
+        args.extend([Cow::from("-p"), Cow::from(prompt.as_str())]);
+    }
+    args.extend(cli_launcher_args(config));
+
+    args
+}
+
+/// Get the launcher arguments that were passed on the command line after `--`.
+fn cli_launcher_args(config: &Config) -> impl Iterator<Item = Cow<'_, str>> {
+    config
+        .args
+        .get_many::<String>("LAUNCHER_ARGS")
+        .into_iter()
+        .flatten()
+        .map(|arg| Cow::from(arg.as_str()))
+}
+
+/// Find the index of the entry that `choice` selects.
+///
+/// If `config.show-tags` is false, the first entry with the same name is found.
+/// Otherwise, a choice without a tag, like one typed in, selects the entry with the same name.
+/// If `config.case-insensitive` is enabled, tags and names are matched case insensitively.
+/// Otherwise, the first entry with an alias that's the same as `choice`, without surrounding
+/// whitespace, is found, ignoring case if `config.case-insensitive` is enabled.
+/// If `config.fuzzy` is enabled, and nothing else matches, [`find_fuzzy_entry`] is tried.
+///
+/// A choice that selects an entry is never run as an ad-hoc command, even if it could be one.
+pub fn find_entry<T: Tag>(choice: &str, entries: &[RunEntry], config: &Config) -> Option<usize> {
+    find_exact_entry::<T>(choice, entries, config)
+        .or_else(|| find_alias(choice, entries, config))
+        .or_else(|| find_typed_tag::<T>(choice, entries, config))
+        .or_else(|| {
+            config
+                .fuzzy
+                .0
+                .then(|| find_fuzzy_entry(choice, entries))
+                .flatten()
+        })
+}
+
+fn find_alias(choice: &str, entries: &[RunEntry], config: &Config) -> Option<usize> {
+    let choice = choice.trim();
+    let is_choice = |alias: &ImStr| {
+        if config.case_insensitive.0 {
+            alias.to_lowercase() == choice.to_lowercase()
+        } else {
+            alias.as_str() == choice
+        }
+    };
+    entries
+        .iter()
+        .position(|entry| entry.options.aliases.iter().any(is_choice))
+}
+
+/// Find the only entry whose name contains the letters of `choice` in order, ignoring case.
+///
+/// If more than one does, the only one whose name starts with `choice` is used, if any.
+fn find_fuzzy_entry(choice: &str, entries: &[RunEntry]) -> Option<usize> {
+    let choice = choice.trim().to_lowercase();
+    if choice.is_empty() {
+        return None;
+    }
+
+    let is_subsequence = |name: &str| {
+        let mut name = name.chars();
+        choice.chars().all(|c| name.any(|n| n == c))
+    };
+    let matches = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| (i, entry.name.to_lowercase()))
+        .filter(|(_, name)| is_subsequence(name))
+        .collect::<Vec<(usize, String)>>();
+
+    match matches.as_slice() {
+        [(only, _)] => Some(*only),
+        _ => {
+            let mut prefixed = matches.iter().filter(|(_, name)| name.starts_with(&choice));
+            match (prefixed.next(), prefixed.next()) {
+                (Some((only, _)), None) => Some(*only),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn find_exact_entry<T: Tag>(choice: &str, entries: &[RunEntry], config: &Config) -> Option<usize> {
+    if !config.show_tags.0 {
+        let width = label_width(entries, config);
+        let matches = |entry: &RunEntry| {
+            let is_choice = |name: &str| {
+                if config.case_insensitive.0 {
+                    name.to_lowercase() == choice.to_lowercase()
+                } else {
+                    name == choice
+                }
+            };
+            is_choice(&entry_label(entry, width, config)) || is_choice(&display_name(entry))
+        };
+        return entries.iter().position(matches);
+    }
+
+    let choice = if config.case_insensitive.0 {
+        Cow::from(choice.to_lowercase())
+    } else {
+        Cow::from(choice)
+    };
+    T::pop_tag(&choice).or_else(|| {
+        entries.iter().position(|entry| {
+            if config.case_insensitive.0 {
+                entry.name.to_lowercase() == *choice
+            } else {
+                entry.name.as_str() == choice
+            }
+        })
+    })
+}
+
+/// Find the entry with the tag typed by hand as `choice`, without the separators around it.
+///
+/// Only shown tags can be typed, so this needs `config.numbered`.
+fn find_typed_tag<T: Tag>(choice: &str, entries: &[RunEntry], config: &Config) -> Option<usize> {
+    if !config.numbered.is_enabled() || !config.show_tags.0 {
+        return None;
+    }
+    let choice = choice.trim();
+    let tag = if config.case_insensitive.0 {
+        T::convert_tag(&choice.to_lowercase())
+    } else {
+        T::convert_tag(choice)
+    };
+    tag.filter(|&id| id < entries.len())
+}
+
+fn history_path(config: &Config) -> Option<PathBuf> {
+    match &config.history {
+        HistoryPath::Disabled => None,
+        HistoryPath::Default => Some(config.dirs.cache_dir().join("history")),
+        HistoryPath::Path(path) => Some(expand_home(path, config)),
+    }
+}
+
+/// Replace a leading `~/` in `path` with the path to the home directory.
+fn expand_home(path: &str, config: &Config) -> PathBuf {
+    if let Some(path) = path.strip_prefix("~/") {
+        config.base_dirs.home_dir().join(path)
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+pub fn build_entries(config: &Config, history: &History) -> anyhow::Result<Vec<RunEntry>> {
+    let mut entries = resolve_entries(config, false)?;
+    filter_conditional(&mut entries, config);
+    sort_entries(&mut entries, history, config.sort);
+
+    Ok(entries)
+}
+
+/// The entries of the main menu, with those from `config.path` and `config.source`,
+/// before any are left out by `when` or `schedule`, and before they're sorted.
+fn resolve_entries(config: &Config, keep_hidden: bool) -> anyhow::Result<Vec<RunEntry>> {
+    let run_entry = |entry: &Entry| {
+        if keep_hidden {
+            RunEntry::with_hidden(entry.clone(), !config.shell.is_enabled())
+        } else {
+            RunEntry::try_from(entry.clone(), !config.shell.is_enabled())
+        }
+    };
+    let mut entries = if let BinPath::Enabled {
+        path,
+        env,
+        replace,
+        recursive,
+        group,
+    } = &config.path
+    {
+        let mut entries = Vec::new();
+        let mut menu_entries = config
+            .entries
+            .iter()
+            .map(|entry| (entry.name(), run_entry(entry)))
+            .collect::<HashMap<ImStr, Option<RunEntry>>>();
+
+        let env_paths = env.then(|| env::var_os("PATH")).flatten();
+        let env_paths = env_paths
+            .as_ref()
+            .map(env::split_paths)
+            .into_iter()
+            .flatten();
+
+        let paths = path
+            .iter()
+            .map(|path| expand_home(path, config))
+            .chain(env_paths);
+
+        let path_bins = paths.filter_map(|path| {
+            let mut files = Vec::new();
+            let mut recur = Vec::new();
+
+            match fs::read_dir(&path) {
+                Ok(dir) => {
+                    if let Err(err) = walk_dir(dir, &mut recur, &mut files) {
+                        return Some(Err(err));
+                    }
+                }
+                Err(_) => return None,
+            }
+
+            if *recursive {
+                while let Some(path) = recur.pop() {
+                    match fs::read_dir(&path) {
+                        Ok(dir) => {
+                            if let Err(err) = walk_dir(dir, &mut recur, &mut files) {
+                                return Some(Err(err));
+                            }
+                        }
+                        Err(_) => continue,
+                    }
+                }
+            }
+
+            Some(Ok(files))
+        });
+
+        for bins in path_bins {
+            let bins = bins?;
+            let mut bin_entries = Vec::new();
+
+            for (path, name) in bins {
+                let path = path.into_string().map_err(|path| {
+                    anyhow!(
+                        "the path `{}` contained invalid unicode",
+                        style_stderr!(bold(), "{}", path.to_string_lossy())
+                    )
+                });
+                let path = match path {
+                    Ok(path) => ImStr::from(path),
+                    Err(err) => {
+                        warn_error(&err);
+                        continue;
+                    }
+                };
+
+                if menu_entries.contains_key(&name) {
+                    if *replace {
+                        let menu_entry = menu_entries.get_mut(&name).expect("unreachable");
+                        if menu_entry.is_some() {
+                            let run_entry = menu_entry.take().expect("unreachable");
+                            bin_entries.push(RunEntry {
+                                name,
+                                action: Action::Run(Run::binary(path)),
+                                options: run_entry.options,
+                                templated: false,
+                            });
+                        }
+                    }
+                } else {
+                    bin_entries.push(RunEntry {
+                        name,
+                        action: Action::Run(Run::binary(path)),
+                        options: EntryOptions {
+                            group: *group,
+                            ..EntryOptions::default()
+                        },
+                        templated: false,
+                    });
+                }
+            }
+
+            entries.extend(bin_entries);
+        }
+
+        entries.extend(
+            config
+                .entries
+                .iter()
+                .filter_map(|entry| menu_entries.remove(&entry.name()).flatten()),
+        );
+
+        entries
+    } else {
+        config
+            .entries
+            .iter()
+            .filter_map(run_entry)
+            .collect::<Vec<RunEntry>>()
+    };
+
+    if let Source::Enabled { source, run, group } = &config.source {
+        let output = command_output(source, "source", config).context(format!(
+            "problem running source command `{}`",
+            style_stderr!(bold(), "{source}")
+        ))?;
+
+        entries.extend(
+            output
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| RunEntry {
+                    name: line.into(),
+                    action: Action::Run(run.clone()),
+                    options: EntryOptions {
+                        group: *group,
+                        ..EntryOptions::default()
+                    },
+                    templated: true,
+                }),
+        );
+    }
+
+    Ok(entries)
+}
+
+/// Run a `kind` of command, like a source, returning its output.
+fn command_output(command: &Run, kind: &str, config: &Config) -> anyhow::Result<String> {
+    let (argv, input) = command_argv(command, config)?;
+    let (program, args) = match argv.split_first() {
+        Some(argv) => argv,
+        None => return Ok(String::new()),
+    };
+
+    info!("running {kind} command {argv:?}");
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context(format!(
+            "failed to run command `{}` (is it installed?)",
+            style_stderr!(bold(), "{program}")
+        ))?;
+
+    if let Some(input) = input {
+        child
+            .stdin
+            .take()
+            .context("failed to establish pipe to shell??")?
+            .write_all(input.as_bytes())
+            .context("failed to write to shell stdin??")?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .context(format!("failed to read {kind} command stdout??"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("{}", stderr.trim_end())
+            .context(format!("the {kind} command failed ({})", output.status)));
+    }
+
+    String::from_utf8(output.stdout).context(format!("the {kind} command output invalid unicode"))
+}
+
+pub fn build_submenu(config: &Config, entries: &[Entry], history: &History) -> Vec<RunEntry> {
+    let mut entries = entries
+        .iter()
+        .filter_map(|entry| RunEntry::try_from(entry.clone(), !config.shell.is_enabled()))
+        .collect::<Vec<RunEntry>>();
+    filter_conditional(&mut entries, config);
+    sort_entries(&mut entries, history, config.sort);
+
+    entries
+}
+
+/// How long `when` commands have to finish before their entries are left out,
+/// and `check` commands have before their entries are run anyway.
+const WHEN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Leave out entries out of their `schedule`, and those whose `when` command fails,
+/// or doesn't finish within [`WHEN_TIMEOUT`].
+///
+/// Every check runs at once, so a menu with many of them waits for the slowest, not the total.
+fn filter_conditional(entries: &mut Vec<RunEntry>, config: &Config) {
+    let mut shown = vec![true; entries.len()];
+    let mut checks = Vec::new();
+    let now = LocalTime::now();
+    for (i, entry) in entries.iter().enumerate() {
+        let schedule = &entry.options.schedule;
+        if !schedule.is_empty() && !schedule.iter().any(|schedule| schedule.contains(now)) {
+            shown[i] = false;
+            continue;
+        }
+        let Some(when) = &entry.options.when else {
+            continue;
+        };
+        match spawn_check(when, &entry.options, config) {
+            Ok(Some(child)) => checks.push((i, child)),
+            Ok(None) => {}
+            Err(err) => {
+                shown[i] = false;
+                warn_error(&err.context(format!(
+                    "problem running `when` command of `{}`",
+                    style_stderr!(bold(), "{}", entry.name)
+                )));
+            }
+        }
+    }
+
+    let deadline = Instant::now() + WHEN_TIMEOUT;
+    loop {
+        checks.retain_mut(|(i, child)| match child.try_wait() {
+            Ok(Some(status)) => {
+                shown[*i] = status.success();
+                false
+            }
+            Ok(None) => true,
+            Err(_) => {
+                shown[*i] = false;
+                false
+            }
+        });
+        if checks.is_empty() {
+            break;
+        }
+
+        if Instant::now() >= deadline {
+            for (i, mut child) in checks.drain(..) {
+                let _ = child.kill();
+                let _ = child.wait();
+                shown[i] = false;
+                warn_error(&anyhow!(
+                    "the `when` command of `{}` didn't finish within {} ms",
+                    style_stderr!(bold(), "{}", entries[i].name),
+                    WHEN_TIMEOUT.as_millis()
+                ));
+            }
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let mut shown = shown.into_iter();
+    entries.retain(|_| shown.next().expect("unreachable"));
+}
+
+/// Start a `when` or `check` command with the entry's directory and environment, and no output.
+fn spawn_check(
+    when: &Run,
+    options: &EntryOptions,
+    config: &Config,
+) -> anyhow::Result<Option<Child>> {
+    let (argv, input) = command_argv(when, config)?;
+    let (program, args) = match argv.split_first() {
+        Some(argv) => argv,
+        None => return Ok(None),
+    };
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(cwd) = options.cwd.as_ref().filter(|cwd| cwd.is_dir()) {
+        command.current_dir(cwd);
+    }
+    for (key, value) in config.env.0.iter().chain(&options.env) {
+        command.env(key.as_str(), env_value(value, config));
+    }
+
+    info!("running check {command:?}");
+    let mut child = command.spawn().context(format!(
+        "failed to run command `{}` (is it installed?)",
+        style_stderr!(bold(), "{program}")
+    ))?;
+
+    if let Some(input) = input {
+        child
+            .stdin
+            .take()
+            .context("failed to establish pipe to shell??")?
+            .write_all(input.as_bytes())
+            .context("failed to write to shell stdin??")?;
+    }
+
+    Ok(Some(child))
+}
+
+/// Sort entries by group, then by how often they've been selected, then as `sort` says.
+fn sort_entries(entries: &mut [RunEntry], history: &History, sort: Sort) {
+    // Stable, so `Sort::None` keeps the order entries were read in.
+    entries.sort_by(|l, r| {
+        let by_group = l.options.group.cmp(&r.options.group).reverse();
+        let by_history = || {
+            history
+                .count(&l.name)
+                .cmp(&history.count(&r.name))
+                .reverse()
+        };
+        let by_lowercase_name = || {
+            l.name
+                .to_ascii_lowercase()
+                .cmp(&r.name.to_ascii_lowercase())
+        };
+        let by_name = || l.name.cmp(&r.name);
+
+        let by_sort = || match sort {
+            Sort::Alpha => by_lowercase_name().then_with(by_name),
+            Sort::AlphaDesc => by_lowercase_name().then_with(by_name).reverse(),
+            Sort::None => Ordering::Equal,
+        };
+
+        by_group.then_with(by_history).then_with(by_sort)
+    });
+
+    // Keep each category together, in the order their first entries are in,
+    // after the entries without a category.
+    let mut categories = HashMap::<ImStr, usize>::default();
+    for entry in entries.iter() {
+        if let Some(category) = &entry.options.category {
+            let next = categories.len() + 1;
+            categories.entry(category.clone()).or_insert(next);
+        }
+    }
+    entries.sort_by_key(|entry| {
+        entry
+            .options
+            .category
+            .as_ref()
+            .map_or(0, |category| categories[category])
+    });
+}
+
+fn is_category_header(choice: &str, entries: &[RunEntry]) -> bool {
+    entries
+        .iter()
+        .filter_map(|entry| entry.options.category.as_deref())
+        .any(|category| category_header(category) == choice)
+}
+
+/// The line shown above the entries in `category`; selecting it does nothing.
+fn category_header(category: &str) -> String {
+    format!("-- {category} --")
+}
+
+fn walk_dir(
+    dir: ReadDir,
+    recur: &mut Vec<PathBuf>,
+    files: &mut Vec<(OsString, ImStr)>,
+) -> anyhow::Result<()> {
+    for entry in dir {
+        let entry = entry.context("error trying to walk PATH directory")?;
+        let filetype = entry.file_type().context("error reading file metadata")?;
+        let follow_symlink_is_dir = || {
+            fs::metadata(entry.path())
+                .context("error reading file metadata")
+                .map(|entry| entry.is_dir())
+                .map_err(|err| {
+                    err.context(format!("symlink `{}` is broken", entry.path().display()))
+                })
+                .unwrap_or_else(|err| {
+                    warn_error(&err);
+                    false
+                })
+        };
+
+        if filetype.is_dir() || follow_symlink_is_dir() {
+            recur.push(entry.path());
+        } else if entry.path().is_executable() {
+            files.push((
+                entry.path().into_os_string(),
+                entry.file_name().to_string_lossy().into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The menu as it's written to the launcher; see [`write_entries`].
+///
+/// Every line has at least its entry's name and a newline, so that much is allocated up front;
+/// [`write_entries`] itself reuses one line buffer, so it doesn't need an estimate.
+pub fn display_entries<T: Tag>(config: &Config, entries: &[RunEntry]) -> String {
+    let names = entries.iter().map(|entry| entry.name.len() + 1).sum();
+    let mut display = Vec::with_capacity(names);
+    write_entries::<T>(config, entries, &mut display).expect("writing to a `Vec` can't fail");
+    String::from_utf8(display).expect("unreachable")
+}
+
+/// Write the menu to `out` one line at a time, so it never has to be held in memory at once.
+pub fn write_entries<T: Tag>(
+    config: &Config,
+    entries: &[RunEntry],
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let mut line = String::new();
+    let launcher = config.launcher.find().ok();
+    let icons = config
+        .icons
+        .is_enabled(launcher.as_deref().unwrap_or_default());
+    let width = label_width(entries, config);
+    // Add a header above the first entry in each category.
+    let push_header = |i: usize, line: &mut String| {
+        let category = entries[i].options.category.as_ref();
+        let previous = i
+            .checked_sub(1)
+            .and_then(|previous| entries[previous].options.category.as_ref());
+        if let Some(category) = category.filter(|_| category != previous) {
+            line.push_str(&category_header(category));
+            line.push('\n');
+        }
+    };
+
+    let mut tag = String::new();
+    for (i, entry) in entries.iter().enumerate() {
+        line.clear();
+        push_header(i, &mut line);
+        let label = entry_label(entry, width, config);
+
+        if !config.show_tags.0 {
+            line.push_str(&label);
+        } else if config.numbered.is_enabled() {
+            tag.clear();
+            if config.tag_padding.0 {
+                T::push_padded_tag(i, entries.len(), &mut tag);
+            } else {
+                T::push_tag(i, &mut tag);
+            }
+
+            if let Some(format) = config.numbered.format() {
+                let rendered = render(format, |placeholder| match placeholder {
+                    "tag" => Ok(ImStr::from(tag.as_str())),
+                    "name" => Ok(ImStr::from(label.as_ref())),
+                    _ => unreachable!("checked when the config was parsed"),
+                })
+                .expect("unreachable");
+                line.push_str(&rendered);
+            } else {
+                line.push_str(&tag);
+                line.push_str(config.numbered.separator());
+                line.push_str(&label);
+            }
+        } else {
+            line.push_str(&label);
+            T::push_tag(i, &mut line);
+        }
+
+        end_line(entry, icons, &mut line);
+        debug!("menu line {:?}", line.trim_end_matches('\n'));
+        out.write_all(line.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// The width names are padded to, so the descriptions after them are aligned.
+fn label_width(entries: &[RunEntry], config: &Config) -> usize {
+    entries
+        .iter()
+        .filter(|entry| entry.options.description.is_some() && config.show_descriptions.0)
+        .map(|entry| visible_len(&hinted_name(entry), config))
+        .max()
+        .unwrap_or(0)
+}
+
+/// How many characters of `text` are shown, not counting any markup.
+fn visible_len(text: &str, config: &Config) -> usize {
+    if config.markup.0 {
+        Markup::strip(text).chars().count()
+    } else {
+        text.chars().count()
+    }
+}
+
+/// The text to show for an entry: its key hint and name, then its description, padded to `width`.
+fn entry_label<'a>(entry: &'a RunEntry, width: usize, config: &Config) -> Cow<'a, str> {
+    let name = hinted_name(entry);
+    let description = entry.options.description.as_ref();
+    match description.filter(|_| config.show_descriptions.0) {
+        Some(description) => {
+            let padding = width.saturating_sub(visible_len(&name, config));
+            Cow::from(format!("{name}{:padding$}  {description}", ""))
+        }
+        None => name,
+    }
+}
+
+/// End a line of the menu, first adding the entry's icon if `icons` is true.
+///
+/// Icons use the metadata protocol of rofi and fuzzel: `\0icon\x1f<name>`.
+fn end_line(entry: &RunEntry, icons: bool, display: &mut String) {
+    if let Some(icon) = entry.options.icon.as_ref().filter(|_| icons) {
+        display.push_str("\0icon\x1f");
+        display.push_str(icon);
+    }
+    display.push('\n');
+}
+
+fn push_disabled(entry: &RunEntry, display: &mut String) {
+    if entry.options.disabled {
+        display.push_str(" (disabled)");
+    }
+}
+
+/// The name of an entry after its key hint, if it has one.
+fn hinted_name(entry: &RunEntry) -> Cow<'_, str> {
+    match &entry.options.key {
+        Some(key) => Cow::from(format!("[{key}] {}", display_name(entry))),
+        None => display_name(entry),
+    }
+}
+
+/// The name of an entry as it's displayed in the menu, without a tag.
+fn display_name(entry: &RunEntry) -> Cow<'_, str> {
+    if entry.options.disabled {
+        let mut name = entry.name.to_string();
+        push_disabled(entry, &mut name);
+        Cow::from(name)
+    } else {
+        Cow::from(entry.name.as_str())
+    }
+}
+
+/// Run the launcher with the input written by `write_menu`, returning its output.
+///
+/// The menu is written while the launcher runs, so it can start reading before it's complete,
+/// unless `config.input-method` is `file`, which writes it to a temporary file first.
+/// If the launcher doesn't exit within `config.timeout`, it's killed and an error is returned.
+pub fn run_launcher(
+    launcher: &str,
+    write_menu: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+    dmenu_args: &[Cow<'_, str>],
+    config: &Config,
+) -> anyhow::Result<String> {
+    // Launchers like rofi only read the menu from stdin in their dmenu mode.
+    let mode_arg = Launcher::mode_arg(launcher)
+        .filter(|mode| !dmenu_args.iter().any(|arg| arg == mode))
+        .map(Cow::from);
+    let dmenu_args = mode_arg
+        .into_iter()
+        .chain(dmenu_args.iter().cloned())
+        .collect::<Vec<Cow<'_, str>>>();
+    info!("running launcher `{launcher}` with arguments {dmenu_args:?}");
+    // The file is removed when it's dropped, after the launcher exits or fails.
+    let (menu_file, write_menu) = match config.input_method {
+        InputMethod::Pipe => (None, Some(write_menu)),
+        InputMethod::File => (Some(MenuFile::write(write_menu)?), None),
+    };
+    let input = match &menu_file {
+        Some(menu_file) => Stdio::from(menu_file.open()?),
+        None => Stdio::piped(),
+    };
+
+    kill_launchers_on_signal();
+    let start = Instant::now();
+    let mut child = Command::new(launcher)
+        .args(
+            dmenu_args
+                .iter()
+                .map(Cow::as_ref)
+                .collect::<Vec<&str>>()
+                .as_slice(),
+        )
+        .stdin(input)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context(format!(
+            "failed to run command `{}` (is it installed?)",
+            style_stderr!(bold(), "{launcher}")
+        ))?;
+    let stdin = match write_menu {
+        Some(write_menu) => Some((
+            child
+                .stdin
+                .take()
+                .context("failed to establish pipe to launcher??")?,
+            write_menu,
+        )),
+        None => None,
+    };
+    let stdout = child
+        .stdout
+        .take()
+        .context("failed to establish pipe to launcher??")?;
+    let stderr = child
+        .stderr
+        .take()
+        .context("failed to establish pipe to launcher??")?;
+
+    let reader = read_pipe(stdout, "stdout");
+    let error_reader = read_pipe(stderr, "stderr");
+    // Waiting in another thread lets the launcher be killed while the menu is still being written.
+    let timeout = config.timeout.duration();
+    let child = Arc::new(Mutex::new(child));
+    running_launchers().push(Arc::downgrade(&child));
+    let waiter = thread::spawn(move || wait_launcher(&child, timeout));
+
+    let written = match stdin {
+        Some((stdin, write_menu)) => {
+            let mut stdin = BufWriter::new(stdin);
+            let written = write_menu(&mut stdin).and_then(|()| stdin.flush());
+            // Closing stdin tells the launcher the menu is complete.
+            drop(stdin);
+            written
+        }
+        None => Ok(()),
+    };
+
+    let status = match waiter.join() {
+        Ok(result) => result?,
+        Err(err) => panic::resume_unwind(err),
+    };
+    debug!(
+        "the launcher exited after {} ms ({status})",
+        start.elapsed().as_millis()
+    );
+    match written {
+        // The launcher may exit without reading the whole menu.
+        Err(err) if err.kind() == ErrorKind::BrokenPipe => {}
+        written => written.context("failed to write to launcher stdin??")?,
+    }
+    let output = match reader.join() {
+        Ok(result) => result?,
+        Err(err) => panic::resume_unwind(err),
+    };
+    let errors = match error_reader.join() {
+        Ok(result) => result?,
+        Err(err) => panic::resume_unwind(err),
+    };
+    info!("the launcher output {:?}", String::from_utf8_lossy(&output));
+
+    // Launchers exit with an error when they're cancelled, usually without printing anything,
+    // so only treat the exit as a failure if it printed an error or was killed by a signal.
+    let errors = String::from_utf8_lossy(&errors);
+    if !errors.trim().is_empty() {
+        info!("the launcher printed errors {errors:?}");
+    }
+    if !status.success() && (!errors.trim().is_empty() || status.code().is_none()) {
+        let error = match status.code() {
+            Some(code) => format!("the launcher exited with code {code}"),
+            None => "the launcher was killed by a signal".to_owned(),
+        };
+        return match errors.trim() {
+            "" => Err(anyhow!(error)),
+            errors => Err(anyhow!(errors.to_owned())).context(error),
+        };
+    }
+
+    if config.lossy_input.0 {
+        return Ok(String::from_utf8_lossy(&output).into_owned());
+    }
+    String::from_utf8(output).map_err(|err| {
+        anyhow!(
+            "the launcher output invalid utf-8 at byte {}; set `{}` to replace it",
+            err.utf8_error().valid_up_to(),
+            style_stderr!(bold(), "config.lossy-input = true")
+        )
+    })
+}
+
+/// A temporary file holding the menu, for `config.input-method = "file"`,
+/// which is removed when it's dropped.
+struct MenuFile {
+    path: PathBuf,
+}
+
+impl MenuFile {
+    fn write(write_menu: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> anyhow::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, AtomicOrdering::Relaxed);
+        let path = env::temp_dir().join(format!("dmm-menu-{}-{count}", process::id()));
+        let error = || {
+            format!(
+                "unable to write the menu to `{}`",
+                style_stderr!(bold(), "{}", path.display())
+            )
+        };
+
+        // Made new, so a file or link that's already there is never written through.
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(error)?;
+        let menu_file = Self { path: path.clone() };
+        let mut file = BufWriter::new(file);
+        write_menu(&mut file)
+            .and_then(|()| file.flush())
+            .with_context(error)?;
+
+        Ok(menu_file)
+    }
+
+    fn open(&self) -> anyhow::Result<fs::File> {
+        fs::File::open(&self.path).context(format!(
+            "unable to read the menu from `{}`",
+            style_stderr!(bold(), "{}", self.path.display())
+        ))
+    }
+}
+
+impl Drop for MenuFile {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            debug!("unable to remove `{}`: {err}", self.path.display());
+        }
+    }
+}
+
+/// Wait for the launcher to exit, killing it if it runs longer than `timeout`.
+///
+/// It's polled rather than waited on, so the lock is free for [`kill_launchers_on_signal`].
+fn wait_launcher(child: &Mutex<Child>, timeout: Option<Duration>) -> anyhow::Result<ExitStatus> {
+    let start = Instant::now();
+    loop {
+        let mut child = child.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(status) = child.try_wait().context("failed to wait for launcher??")? {
+            return Ok(status);
+        }
+        if let Some(timeout) = timeout.filter(|timeout| start.elapsed() >= *timeout) {
+            // Killing the launcher closes its end of the pipes, so writing the menu stops.
+            child.kill().context("failed to kill launcher??")?;
+            child.wait().context("failed to wait for launcher??")?;
+
+            return Err(anyhow!(
+                "the launcher didn't exit within {} ms; see `{}`",
+                timeout.as_millis(),
+                style_stderr!(bold(), "config.timeout")
+            ));
+        }
+        drop(child);
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// The launchers that are running, to kill if `dmm` is interrupted.
+fn running_launchers() -> MutexGuard<'static, Vec<Weak<Mutex<Child>>>> {
+    static LAUNCHERS: Mutex<Vec<Weak<Mutex<Child>>>> = Mutex::new(Vec::new());
+
+    let mut launchers = LAUNCHERS.lock().unwrap_or_else(PoisonError::into_inner);
+    launchers.retain(|launcher| launcher.strong_count() > 0);
+    launchers
+}
+
+/// Kill any running launchers and exit when `dmm` gets `SIGINT` or `SIGTERM`,
+/// so a window like dmenu's isn't left open without anything to read its selection.
+///
+/// Exiting also stops writing the menu. The handler is only installed once.
+#[cfg(unix)]
+fn kill_launchers_on_signal() {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+    use std::sync::Once;
+
+    static HANDLER: Once = Once::new();
+    HANDLER.call_once(|| {
+        let mut signals = match Signals::new([SIGINT, SIGTERM]) {
+            Ok(signals) => signals,
+            Err(err) => {
+                warn_error(&anyhow!(err).context("unable to handle interrupts"));
+                return;
+            }
+        };
+
+        thread::spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                let launchers = running_launchers()
+                    .drain(..)
+                    .filter_map(|launcher| launcher.upgrade())
+                    .collect::<Vec<_>>();
+                // Locked until exiting, so a waiting thread can't report the launcher as killed.
+                let _locked = launchers
+                    .iter()
+                    .map(|launcher| {
+                        let mut launcher = launcher.lock().unwrap_or_else(PoisonError::into_inner);
+                        debug!("killing launcher {} after signal {signal}", launcher.id());
+                        // It may have already exited, which is just as good.
+                        let _ = launcher.kill();
+                        let _ = launcher.wait();
+                        launcher
+                    })
+                    .collect::<Vec<_>>();
+                process::exit(128 + signal);
+            }
+        });
+    });
+}
+
+#[cfg(not(unix))]
+fn kill_launchers_on_signal() {}
+
+/// Read all of `pipe` in another thread, so the launcher can't block on a full pipe.
+fn read_pipe(
+    mut pipe: impl Read + Send + 'static,
+    name: &'static str,
+) -> JoinHandle<anyhow::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        pipe.read_to_end(&mut output)
+            .with_context(|| format!("failed to read launcher {name}??"))
+            .map(|_| output)
+    })
+}
+
+/// Run each command in order, returning the exit code `dmm` should exit with.
+///
+/// Unless `config.parallel` is enabled, each command is waited on before the next is run.
+/// Unless `config.wait` is enabled, the last command (or with `config.parallel`, every command)
+/// isn't waited on, and the exit code is always 0.
+pub fn run_commands(commands: &[Choice], config: &Config) -> anyhow::Result<i32> {
+    let mut exit_code = 0;
+    let mut children = Vec::new();
+
+    // Returns whether to keep running commands.
+    let mut record = |entry: &Choice, result: anyhow::Result<Option<ExitStatus>>| {
+        let failure = match result {
+            Ok(Some(status)) if !status.success() => {
+                config.wait.is_enabled().then(|| status.code().unwrap_or(1))
+            }
+            Ok(_) => None,
+            Err(err) => {
+                let err = err.context(format!(
+                    "can't run `{}`",
+                    style_stderr!(bold(), "{}", entry.name)
+                ));
+                warn_error(&err);
+                if config.notify.0 {
+                    notify_error(&entry.name, &err);
+                }
+                config.wait.is_enabled().then_some(1)
+            }
+        };
+
+        if let Some(code) = failure {
+            if exit_code == 0 {
+                exit_code = code;
+            }
+            if let Wait::Enabled { keep_going: false } = config.wait {
+                return false;
+            }
+        }
+        true
+    };
+
+    // Nothing is left to do after the only command, unless the menu is shown again.
+    let replace = config.exec.0
+        && commands.len() == 1
+        && config.post.0.is_none()
+        && !commands.iter().any(|command| command.options.sticky)
+        && !config.args.get_flag("watch")
+        && !config.capture.0;
+
+    for (i, entry) in commands.iter().enumerate() {
+        let is_last = i + 1 == commands.len();
+        let result = match spawn_command(entry, replace, config) {
+            Ok(Some(child)) if config.parallel.0 => {
+                children.push((entry, child));
+                continue;
+            }
+            // Captured output can only be read by waiting for it.
+            Ok(Some(child)) if config.wait.is_enabled() || config.capture.0 || !is_last => {
+                wait_command(entry, &entry.run, child, config).map(Some)
+            }
+            Ok(_) => Ok(None),
+            Err(err) => Err(err),
+        };
+
+        if !record(entry, result) {
+            break;
+        }
+    }
+
+    if config.wait.is_enabled() || config.capture.0 {
+        for (entry, child) in children {
+            record(
+                entry,
+                wait_command(entry, &entry.run, child, config).map(Some),
+            );
+        }
+    }
+
+    Ok(exit_code)
+}
+
+/// Wait for `run`, one of the entry's commands, logging its output if `config.capture` is set.
+fn wait_command(
+    entry: &Choice,
+    run: &Run,
+    mut child: Child,
+    config: &Config,
+) -> anyhow::Result<ExitStatus> {
+    let error = || {
+        format!(
+            "failed to wait for command `{}`",
+            style_stderr!(bold(), "{run}")
+        )
+    };
+    if !config.capture.0 {
+        return child.wait().with_context(error);
+    }
+
+    let output = child.wait_with_output().with_context(error)?;
+    log_output(entry, "stdout", &output.stdout);
+    log_output(entry, "stderr", &output.stderr);
+    Ok(output.status)
+}
+
+/// How many lines of each captured output are logged.
+const CAPTURED_LINES: usize = 20;
+
+fn log_output(entry: &Choice, stream: &str, output: &[u8]) {
+    let output = String::from_utf8_lossy(output);
+    let mut lines = output.lines();
+    for line in lines.by_ref().take(CAPTURED_LINES) {
+        info!("`{}` {stream}: {line}", entry.name);
+    }
+    let rest = lines.count();
+    if rest > 0 {
+        info!("`{}` {stream}: ({rest} more lines)", entry.name);
+    }
+}
+
+/// Start running a command, returning `None` if there was nothing to run.
+///
+/// For an entry with `steps`, every step but the last is run to completion first,
+/// stopping with an error at the first that fails unless `ignore-errors` is set.
+///
+/// If `replace` is true, `dmm` is replaced with the (last) command instead, if it can be,
+/// so this only returns if it can't be run.
+pub fn spawn_command(
+    entry: &Choice,
+    replace: bool,
+    config: &Config,
+) -> anyhow::Result<Option<Child>> {
+    if let Some(check) = &entry.options.check {
+        if check_succeeds(entry, check, config) {
+            info!(
+                "skipping `{}`, since its `check` command succeeded",
+                entry.name
+            );
+            return Ok(None);
+        }
+    }
+    let edited;
+    let run = if config.editable.0 {
+        match edit(entry, config)? {
+            Some(run) => {
+                edited = run;
+                &edited
+            }
+            None => return Ok(None),
+        }
+    } else {
+        &entry.run
+    };
+    if entry.options.confirm && !confirm(entry, config)? {
+        return Ok(None);
+    }
+
+    let (steps, ignore_errors) = match run {
+        Run::Steps {
+            steps,
+            ignore_errors,
+        } => (steps.as_slice(), *ignore_errors),
+        run => return spawn_run(entry, run, replace, config),
+    };
+    let Some((last, steps)) = steps.split_last() else {
+        return Ok(None);
+    };
+
+    for (i, step) in steps.iter().enumerate() {
+        let result = spawn_run(entry, step, false, config).and_then(|child| {
+            let Some(child) = child else {
+                return Ok(());
+            };
+            let status = wait_command(entry, step, child, config)?;
+            if !status.success() {
+                return Err(anyhow!("the command failed ({status})"));
+            }
+            Ok(())
+        });
+
+        if let Err(err) = result {
+            let err = err.context(format!(
+                "problem running step {} of `{}`, `{}`",
+                i + 1,
+                style_stderr!(bold(), "{}", entry.name),
+                style_stderr!(bold(), "{step}")
+            ));
+            if !ignore_errors {
+                return Err(err);
+            }
+            warn_error(&err);
+        }
+    }
+
+    spawn_run(entry, last, replace, config)
+}
+
+/// Whether an entry's `check` command succeeds, so its command should be skipped.
+///
+/// A check that can't be run, or doesn't finish within [`WHEN_TIMEOUT`], doesn't succeed,
+/// so the command is run as if there were no check.
+fn check_succeeds(entry: &Choice, check: &Run, config: &Config) -> bool {
+    let error = || {
+        format!(
+            "problem running `check` command of `{}`",
+            style_stderr!(bold(), "{}", entry.name)
+        )
+    };
+    let mut child = match spawn_check(check, &entry.options, config) {
+        Ok(Some(child)) => child,
+        Ok(None) => return false,
+        Err(err) => {
+            warn_error(&err.context(error()));
+            return false;
+        }
+    };
+
+    let deadline = Instant::now() + WHEN_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return status.success(),
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                warn_error(&anyhow!(
+                    "the `check` command of `{}` didn't finish within {} ms",
+                    style_stderr!(bold(), "{}", entry.name),
+                    WHEN_TIMEOUT.as_millis()
+                ));
+                return false;
+            }
+            Err(err) => {
+                warn_error(&anyhow!(err).context(error()));
+                return false;
+            }
+        }
+    }
+}
+
+/// Start running one command of an entry; `run` must not be [`Run::Steps`].
+///
+/// If `replace` is true, `dmm` is replaced with the command, unless it needs input piped to it.
+fn spawn_run(
+    entry: &Choice,
+    run: &Run,
+    replace: bool,
+    config: &Config,
+) -> anyhow::Result<Option<Child>> {
+    let (argv, input) = command_argv(run, config)?;
+    if argv.is_empty() {
+        return Ok(None);
+    }
+
+    let in_terminal = entry.options.terminal.unwrap_or(config.terminal.0);
+    let argv = if in_terminal {
+        if input.is_some() {
+            return Err(anyhow!(
+                "commands can't be piped to a shell running in a terminal; \
+                 set `config.shell.piped = false` or disable `terminal`"
+            ));
+        }
+        config
+            .terminal_command
+            .0
+            .iter()
+            .map(ImStr::as_str)
+            .chain(argv)
+            .collect()
+    } else {
+        argv
+    };
+
+    let mut command = match argv.split_first() {
+        Some((program, args)) => {
+            let mut command = Command::new(program);
+            command.args(args);
+            command
+        }
+        None => return Ok(None),
+    };
+
+    if input.is_some() {
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+    }
+    if config.capture.0 {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+
+    if let Some(cwd) = &entry.options.cwd {
+        if !cwd.is_dir() {
+            return Err(anyhow!(
+                "the working directory `{}` does not exist",
+                style_stderr!(bold(), "{}", cwd.display())
+            ));
+        }
+        command.current_dir(cwd);
+    }
+
+    for (key, value) in config.env.0.iter().chain(&entry.options.env) {
+        command.env(key.as_str(), env_value(value, config));
+    }
+
+    if config.detach.0 {
+        detach(&mut command);
+    }
+
+    info!("running {command:?}");
+    if let Some(input) = input {
+        info!("piping {:?} to its stdin", input.as_str());
+    }
+    let program = command.get_program().to_string_lossy();
+    let program = style_stderr!(bold(), "{program}");
+    let spawn_error = match run {
+        _ if in_terminal => format!("failed to run terminal `{program}` (is it installed?)"),
+        Run::Shell(run) => format!(
+            "failed to run shell `{program}` for the command `{}` (is it installed?)",
+            style_stderr!(bold(), "{run}")
+        ),
+        Run::Bare(_) | Run::Steps { .. } => {
+            format!("failed to run command `{program}` (is it installed?)")
+        }
+    };
+    // Set after logging the command, since they're the same for every selected command.
+    command
+        .env("DMENU_NAME", entry.name.as_str())
+        .env("DMENU_COUNT", entry.count.to_string());
+    if let Some(id) = entry.id {
+        command.env("DMENU_INDEX", id.to_string());
+    }
+    if let Some(tag) = &entry.tag {
+        command.env("DMENU_TAG", tag.as_str());
+    }
+    if replace && input.is_none() {
+        if let Some(err) = exec(&mut command) {
+            return Err(err).context(spawn_error);
+        }
+    }
+    let mut child = command.spawn().context(spawn_error)?;
+
+    if let Some(input) = input {
+        child
+            .stdin
+            .take()
+            .context("failed to establish pipe to shell??")?
+            .write_all(input.as_bytes())
+            .context("failed to write to shell stdin??")?;
+    }
+
+    Ok(Some(child))
+}
+
+/// Start `command` in a new process group, so it isn't sent signals meant for `dmm`'s group,
+/// like when the window manager kills the keybind that started `dmm`.
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn detach(_: &mut Command) {}
+
+/// The value to set an environment variable from `env` to, with `${VAR}`s expanded,
+/// unless `config.expand-env` already expanded them when the config was loaded.
+fn env_value(value: &str, config: &Config) -> String {
+    match config.expand_env {
+        ExpandEnv::Enabled { .. } => value.to_owned(),
+        ExpandEnv::Disabled => expand_env(value),
+    }
+}
+
+/// Replace `dmm` with `command`, returning why if it can't be run.
+///
+/// Returns `None` where processes can't be replaced, so the command should be spawned instead.
+#[cfg(unix)]
+fn exec(command: &mut Command) -> Option<io::Error> {
+    use std::os::unix::process::CommandExt;
+
+    Some(command.exec())
+}
+
+#[cfg(not(unix))]
+fn exec(_: &mut Command) -> Option<io::Error> {
+    None
+}
+
+/// Show the entry's command in the launcher, to edit it before it's run.
+///
+/// Launchers that can start with a search start with the command to edit; others show it
+/// as the only option, to run it as it is or to type another. Returns `None` if cancelled.
+fn edit(entry: &Choice, config: &Config) -> anyhow::Result<Option<Run>> {
+    let launcher = &config.launcher.find()?;
+    let command = entry.run.to_string();
+    let select = Select(Some(ImStr::from(command.as_str())));
+    let mut args = config.dmenu.args(launcher);
+    args.extend(config.launcher_args.args(launcher));
+    args.extend(cli_launcher_args(config));
+    args.extend([Cow::from("-p"), Cow::from(format!("Edit {}:", entry.name))]);
+    args.extend(select.args(launcher).into_iter().flatten());
+
+    let edited = run_launcher(
+        launcher,
+        |stdin| writeln!(stdin, "{command}"),
+        &args,
+        config,
+    )
+    .context(format!(
+        "problem running launcher `{}` to edit the command",
+        style_stderr!(bold(), "{launcher}")
+    ))?;
+
+    match edited.trim_end_matches('\n') {
+        "" => Ok(None),
+        edited if edited == command => Ok(Some(entry.run.clone())),
+        edited => typed_run(edited, config).map(Some),
+    }
+}
+
+/// Ask for confirmation to run a command with the launcher; only an explicit `yes` confirms.
+fn confirm(entry: &Choice, config: &Config) -> anyhow::Result<bool> {
+    let launcher = &config.launcher.find()?;
+    let prompt = match &entry.options.confirm_prompt {
+        Some(prompt) => Cow::from(prompt.as_str()),
+        None => Cow::from(format!("Run {}?", entry.name)),
+    };
+    let mut args = config.dmenu.args(launcher);
+    args.extend(config.launcher_args.args(launcher));
+    args.extend(cli_launcher_args(config));
+    args.extend([Cow::from("-p"), prompt]);
+
+    let answer = run_launcher(
+        launcher,
+        |stdin| stdin.write_all(b"yes\nno\n"),
+        &args,
+        config,
+    )
+    .context(format!(
+        "problem running launcher `{}` to confirm",
+        style_stderr!(bold(), "{launcher}")
+    ))?;
+
+    Ok(answer.trim_end_matches('\n') == "yes")
+}
+
+/// Get the arguments to run a command with, and the input to pipe to it, if any.
+///
+/// The arguments are empty if there is nothing to run.
+fn command_argv<'a>(
+    run: &'a Run,
+    config: &'a Config,
+) -> anyhow::Result<(Vec<&'a str>, Option<&'a ImStr>)> {
+    match run {
+        Run::Bare(run) => Ok((run.iter().map(ImStr::as_str).collect(), None)),
+        Run::Steps { .. } => unreachable!("each step is run on its own"),
+        Run::Shell(run) if run.is_empty() => Ok((Vec::new(), None)),
+        Run::Shell(run) => match &config.shell {
+            Shell::Disabled => Err(anyhow!(
+                "shell execution is disabled; to enable, set `config.shell = true`"
+            )),
+            Shell::Enabled { shell, piped } => {
+                let shell = shell.iter().map(ImStr::as_str);
+                if *piped {
+                    Ok((shell.collect(), Some(run)))
+                } else {
+                    Ok((shell.chain([run.as_str()]).collect(), None))
+                }
+            }
+        },
+    }
+}
+
+/// Send a desktop notification that `name` couldn't be run, with the whole error as its body.
+///
+/// A notification that can't be sent is only a warning, since the error was already reported.
+fn notify_error(name: &str, err: &anyhow::Error) {
+    let body = err
+        .chain()
+        .map(|cause| strip_ansi(&cause.to_string()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut command = Command::new("notify-send");
+    command
+        .args(["--app-name=dmm", "--urgency=critical", "--"])
+        .arg(format!("dmm: can't run {name}"))
+        .arg(body)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null());
+
+    info!("running {command:?}");
+    let result = command
+        .status()
+        .context("failed to run `notify-send` (is it installed?)")
+        .and_then(|status| {
+            if !status.success() {
+                return Err(anyhow!("`notify-send` failed ({status})"));
+            }
+            Ok(())
+        });
+    if let Err(err) = result {
+        warn_error(&err.context("unable to send a notification"));
+    }
+}
+
+/// Remove the escape sequences `style_stderr!` adds, which a notification would show as is.
+fn strip_ansi(styled: &str) -> String {
+    let mut plain = String::with_capacity(styled.len());
+    let mut chars = styled.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(char::is_ascii_alphabetic);
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+pub fn display_error(err: &anyhow::Error) {
+    report_error(
+        err,
+        "error:",
+        ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true),
+    );
+}
+
+pub fn warn_error(err: &anyhow::Error) {
+    report_error(
+        err,
+        "warning:",
+        ColorSpec::new().set_fg(Some(Color::Yellow)).set_bold(true),
+    );
+}
+
+fn report_error(err: &anyhow::Error, name: &str, style: &ColorSpec) {
+    let mut stderr = StandardStream::stderr(stderr_color_choice());
+    let mut chain = err.chain();
+    let err = chain.next().unwrap();
+
+    write_style!(stderr, style, "{name} ");
+    eprintln!("{err}");
+    for cause in chain {
+        write_style!(stderr, style, "  - ");
+        eprintln!("{cause}");
+    }
+    eprintln!();
+}
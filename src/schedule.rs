@@ -0,0 +1,189 @@
+use std::fmt::{self, Display};
+
+use anyhow::{anyhow, Context};
+
+use crate::imstr::ImStr;
+use crate::style::{bold, style_stderr};
+
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// When an entry is shown, like `Mon-Fri 09:00-10:00`: days, a time of day, or both.
+///
+/// Days are ranges or single days separated by commas, like `Mon,Wed-Fri`, in any case.
+/// A time range that ends before it starts runs past midnight, like `22:00-02:00`,
+/// and includes its start but not its end.
+///
+/// ```
+/// use dmm::schedule::{LocalTime, Schedule};
+///
+/// let standup = Schedule::parse("Mon-Fri 09:00-10:00").unwrap();
+/// let monday = |hour: u16, minute: u16| LocalTime { weekday: 0, minute: hour * 60 + minute };
+/// assert!(standup.contains(monday(9, 30)));
+/// assert!(!standup.contains(monday(10, 0)));
+/// assert!(!standup.contains(LocalTime { weekday: 5, minute: 9 * 60 }));
+///
+/// let late = Schedule::parse("22:00-02:00").unwrap();
+/// assert!(late.contains(monday(23, 0)) && late.contains(monday(1, 0)));
+/// assert!(Schedule::parse("sat,SUN").unwrap().contains(LocalTime { weekday: 6, minute: 0 }));
+///
+/// assert!(Schedule::parse("Someday").is_err());
+/// assert!(Schedule::parse("09:00-25:00").is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    source: ImStr,
+    /// Whether each day, from Monday, is included.
+    days: [bool; 7],
+    /// The start and end of the time range, in minutes since midnight.
+    time: Option<(u16, u16)>,
+}
+
+impl Schedule {
+    pub fn parse(schedule: &str) -> anyhow::Result<Self> {
+        let error = || {
+            format!(
+                "invalid schedule `{}`; expected something like `{}`",
+                style_stderr!(bold(), "{schedule}"),
+                style_stderr!(bold(), "Mon-Fri 09:00-10:00"),
+            )
+        };
+
+        let mut days = None;
+        let mut time = None;
+        for part in schedule.split_whitespace() {
+            if part.starts_with(|c: char| c.is_ascii_digit()) && time.is_none() {
+                time = Some(parse_times(part).with_context(error)?);
+            } else if days.is_none() {
+                days = Some(parse_days(part).with_context(error)?);
+            } else {
+                return Err(anyhow!("unexpected `{part}`")).with_context(error);
+            }
+        }
+        if days.is_none() && time.is_none() {
+            return Err(anyhow!("it's empty")).with_context(error);
+        }
+
+        Ok(Self {
+            source: ImStr::from(schedule),
+            days: days.unwrap_or([true; 7]),
+            time,
+        })
+    }
+
+    pub fn contains(&self, now: LocalTime) -> bool {
+        let today = usize::from(now.weekday);
+        let yesterday = (today + 6) % 7;
+        match self.time {
+            None => self.days[today],
+            Some((start, end)) if start <= end => {
+                self.days[today] && (start..end).contains(&now.minute)
+            }
+            // Past midnight, a range that started the day before is still running.
+            Some((start, end)) => {
+                (self.days[today] && now.minute >= start)
+                    || (self.days[yesterday] && now.minute < end)
+            }
+        }
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn parse_days(days: &str) -> anyhow::Result<[bool; 7]> {
+    let day = |day: &str| {
+        DAYS.iter()
+            .position(|known| known.eq_ignore_ascii_case(day))
+            .ok_or_else(|| anyhow!("`{day}` isn't a day"))
+    };
+
+    let mut included = [false; 7];
+    for range in days.split(',') {
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (day(start)?, day(end)?),
+            None => (day(range)?, day(range)?),
+        };
+        // A range like `Sat-Mon` wraps around the end of the week.
+        let mut i = start;
+        loop {
+            included[i] = true;
+            if i == end {
+                break;
+            }
+            i = (i + 1) % 7;
+        }
+    }
+
+    Ok(included)
+}
+
+fn parse_times(times: &str) -> anyhow::Result<(u16, u16)> {
+    let time = |time: &str| {
+        let (hour, minute) = time
+            .split_once(':')
+            .ok_or_else(|| anyhow!("`{time}` isn't a time like `09:00`"))?;
+        let minute_len = minute.len();
+        match (hour.parse::<u16>(), minute.parse::<u16>()) {
+            (Ok(hour), Ok(minute)) if hour < 24 && minute < 60 && minute_len == 2 => {
+                Ok(hour * 60 + minute)
+            }
+            _ => Err(anyhow!("`{time}` isn't a time like `09:00`")),
+        }
+    };
+
+    let (start, end) = times
+        .split_once('-')
+        .ok_or_else(|| anyhow!("`{times}` isn't a range of times like `09:00-10:00`"))?;
+    Ok((time(start)?, time(end)?))
+}
+
+/// A day of the week, counted from Monday as 0, and a time of day, in the local time zone.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalTime {
+    pub weekday: u8,
+    /// Minutes since midnight.
+    pub minute: u16,
+}
+
+impl LocalTime {
+    /// The current time, in the local time zone, or in UTC if it can't be found.
+    #[cfg(unix)]
+    pub fn now() -> Self {
+        // SAFETY: `time` allows a null pointer, and then only returns the time.
+        let now = unsafe { libc::time(std::ptr::null_mut()) };
+        // SAFETY: `tm` is plain integers, and a pointer that may be null, so it can be zeroed;
+        // `localtime_r` only writes to the `tm` it's given.
+        let mut tm = unsafe { std::mem::zeroed::<libc::tm>() };
+        if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+            return Self::utc_now();
+        }
+
+        Self {
+            // `tm_wday` counts from Sunday.
+            weekday: u8::try_from((tm.tm_wday + 6) % 7).unwrap_or(0),
+            minute: u16::try_from(tm.tm_hour * 60 + tm.tm_min).unwrap_or(0),
+        }
+    }
+
+    /// The current time, in UTC, since the local time zone isn't known.
+    #[cfg(not(unix))]
+    pub fn now() -> Self {
+        Self::utc_now()
+    }
+
+    fn utc_now() -> Self {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let days = seconds / 86_400;
+
+        Self {
+            // The epoch was a Thursday.
+            weekday: u8::try_from((days + 3) % 7).unwrap_or(0),
+            minute: u16::try_from(seconds % 86_400 / 60).unwrap_or(0),
+        }
+    }
+}
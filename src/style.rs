@@ -1,4 +1,6 @@
+use std::env;
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use is_terminal::IsTerminal;
 use termcolor::{ColorChoice, ColorSpec, StandardStream, WriteColor};
@@ -9,8 +11,21 @@ pub fn bold() -> ColorSpec {
     style
 }
 
+static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Disable color for the rest of the program, as with `--no-color`.
+pub fn disable_color() {
+    COLOR_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether color may be used at all: it's disabled by `--no-color`, or if `NO_COLOR` is set.
+pub fn color_allowed() -> bool {
+    !COLOR_DISABLED.load(Ordering::Relaxed)
+        && env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty())
+}
+
 pub fn stderr_color_choice() -> ColorChoice {
-    if io::stderr().is_terminal() {
+    if color_allowed() && io::stderr().is_terminal() {
         ColorChoice::Auto
     } else {
         ColorChoice::Never
@@ -18,11 +33,15 @@ pub fn stderr_color_choice() -> ColorChoice {
 }
 
 pub fn stderr_color_enabled() -> bool {
-    io::stderr().is_terminal() && StandardStream::stderr(ColorChoice::Auto).supports_color()
+    color_allowed()
+        && io::stderr().is_terminal()
+        && StandardStream::stderr(ColorChoice::Auto).supports_color()
 }
 
 pub fn stdout_color_enabled() -> bool {
-    io::stdout().is_terminal() && StandardStream::stdout(ColorChoice::Auto).supports_color()
+    color_allowed()
+        && io::stdout().is_terminal()
+        && StandardStream::stdout(ColorChoice::Auto).supports_color()
 }
 
 #[macro_export]
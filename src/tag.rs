@@ -4,8 +4,10 @@ use std::{cell::RefCell, fmt::Write};
 const ZERO: char = '\u{200b}';
 /// `Zero width joiner` character.
 const ONE: char = '\u{200d}';
-/// `Zero width non joiner` character.
-const SEP: char = '\u{200c}';
+/// `Zero width non joiner` character, which surrounds each tag.
+///
+/// It can't be used in `config.numbered.separator`, since a line must have only one tag.
+pub const SEP: char = '\u{200c}';
 
 thread_local! {
     /// Persistant [`String`] buffer to minimize allocations.
@@ -30,16 +32,40 @@ pub trait Tag {
     /// Convert the provided tag to its value as a [`usize`].
     fn convert_tag(tag: &str) -> Option<usize>;
 
-    /// Find the first tag, returning it and any part of the string after the tag.
+    /// Like [`Tag::push_tag`], but padded to the width of the largest tag less than `len`.
+    ///
+    /// Tags that can't be padded without changing their value are pushed unpadded.
+    fn push_padded_tag(num: usize, len: usize, out: &mut String) {
+        let _ = len;
+        Self::push_tag(num, out);
+    }
+
+    /// Find the tag in a line of the menu, returning its value.
+    ///
+    /// The whole tag between its separators must be valid, and the line must have only one tag,
+    /// so a malformed line is `None` rather than a match for some other entry.
+    ///
+    /// ```
+    /// use dmm::tag::{Alpha, Tag};
+    ///
+    /// let (mut a, mut aa) = (String::new(), String::new());
+    /// Alpha::push_tag(0, &mut a);
+    /// Alpha::push_tag(26, &mut aa);
+    /// assert_eq!(Alpha::pop_tag(&format!("{a}: firefox")), Some(0));
+    /// assert_eq!(Alpha::pop_tag(&format!("{aa}: htop")), Some(26));
+    ///
+    /// // Missing the closing separator, with an extra tag, or with a partly valid tag.
+    /// assert_eq!(Alpha::pop_tag("\u{200c}aa: htop"), None);
+    /// assert_eq!(Alpha::pop_tag(&format!("{a}{aa}: htop")), None);
+    /// assert_eq!(Alpha::pop_tag("\u{200c}a a\u{200c}: htop"), None);
+    /// ```
     fn pop_tag(string: &str) -> Option<usize> {
-        string.find(SEP).and_then(|first_sep| {
-            let start = first_sep + SEP.len_utf8();
-            let string = &string[start..];
-            string.find(SEP).and_then(|end| {
-                let tag = &string[..end];
-                Self::convert_tag(tag)
-            })
-        })
+        let mut parts = string.split(SEP);
+        let (_, tag, _) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() {
+            return None;
+        }
+        Self::convert_tag(tag)
     }
 }
 
@@ -85,6 +111,17 @@ impl Tag for Binary {
 }
 
 /// Decimal encoded ascii.
+///
+/// ```
+/// use dmm::tag::{Decimal, Tag};
+///
+/// let mut tag = String::new();
+/// Decimal::push_padded_tag(7, 120, &mut tag);
+/// assert_eq!(tag.trim_matches('\u{200c}'), "007");
+/// assert_eq!(Decimal::convert_tag(&tag), Some(7));
+/// assert_eq!(Decimal::convert_tag("7"), Some(7));
+/// assert_eq!(Decimal::convert_tag("+7"), None);
+/// ```
 pub struct Decimal;
 
 impl Tag for Decimal {
@@ -92,8 +129,102 @@ impl Tag for Decimal {
         write!(out, "{SEP}{num}{SEP}").unwrap();
     }
 
+    fn push_padded_tag(num: usize, len: usize, out: &mut String) {
+        let width = len.saturating_sub(1).to_string().len();
+        write!(out, "{SEP}{num:0width$}{SEP}").unwrap();
+    }
+
     fn convert_tag(tag: &str) -> Option<usize> {
         let tag = tag.trim_matches(SEP);
+        // Parsing alone would accept a leading `+`.
+        if !tag.bytes().all(|digit| digit.is_ascii_digit()) {
+            return None;
+        }
         tag.parse().ok()
     }
 }
+
+/// Lowercase ascii letters, `a` to `z`, then `aa`, `ab`, and so on.
+///
+/// ```
+/// use dmm::tag::{Alpha, Tag};
+///
+/// let mut tag = String::new();
+/// Alpha::push_tag(27, &mut tag);
+/// assert_eq!(tag.trim_matches('\u{200c}'), "ab");
+///
+/// for num in [0, 25, 26, 701, 702, 18277] {
+///     let mut tag = String::new();
+///     Alpha::push_tag(num, &mut tag);
+///     assert_eq!(Alpha::convert_tag(&tag), Some(num));
+/// }
+/// assert_eq!(Alpha::convert_tag("zz"), Some(701));
+/// assert_eq!(Alpha::convert_tag("a1"), None);
+/// ```
+pub struct Alpha;
+
+impl Tag for Alpha {
+    fn push_tag(num: usize, out: &mut String) {
+        with_buf! {buf;
+            let mut num = num + 1;
+            while num > 0 {
+                num -= 1;
+                buf.push(char::from(b'a' + u8::try_from(num % 26).expect("unreachable")));
+                num /= 26;
+            }
+
+            out.push(SEP);
+            out.extend(buf.chars().rev());
+            out.push(SEP);
+        }
+    }
+
+    fn convert_tag(tag: &str) -> Option<usize> {
+        let tag = tag.trim_matches(SEP);
+        if tag.is_empty() {
+            return None;
+        }
+
+        let num = tag.bytes().try_fold(0_usize, |num, letter| {
+            let digit = match letter {
+                b'a'..=b'z' => usize::from(letter - b'a') + 1,
+                _ => return None,
+            };
+            num.checked_mul(26)?.checked_add(digit)
+        })?;
+
+        Some(num - 1)
+    }
+}
+
+/// Hexadecimal encoded ascii; case insensitive.
+///
+/// ```
+/// use dmm::tag::{Hex, Tag};
+///
+/// for num in 0..=1000 {
+///     let mut tag = String::new();
+///     Hex::push_tag(num, &mut tag);
+///     assert_eq!(Hex::convert_tag(&tag), Some(num));
+///     assert_eq!(Hex::convert_tag(&tag.to_uppercase()), Some(num));
+/// }
+/// assert_eq!(Hex::convert_tag("3E8"), Some(1000));
+/// assert_eq!(Hex::convert_tag("g"), None);
+/// assert_eq!(Hex::convert_tag("+a"), None);
+/// ```
+pub struct Hex;
+
+impl Tag for Hex {
+    fn push_tag(num: usize, out: &mut String) {
+        write!(out, "{SEP}{num:x}{SEP}").unwrap();
+    }
+
+    fn convert_tag(tag: &str) -> Option<usize> {
+        let tag = tag.trim_matches(SEP);
+        // Parsing alone would accept a leading `+`.
+        if !tag.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return None;
+        }
+        usize::from_str_radix(tag, 16).ok()
+    }
+}
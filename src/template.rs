@@ -0,0 +1,228 @@
+use std::env;
+
+use anyhow::anyhow;
+
+use crate::imstr::ImStr;
+use crate::style::{bold, style_stderr};
+
+/// Replace each `{placeholder}` in `template` with the value `resolve` returns for its name.
+///
+/// `{{` and `}}` are escapes for literal braces.
+/// Placeholders are resolved in order of appearance;
+/// any error returned by `resolve` stops rendering and is returned.
+///
+/// ```
+/// use dmm::imstr::ImStr;
+/// use dmm::template::render;
+///
+/// let rendered = render("notify-send {name} {{literal}}", |placeholder| match placeholder {
+///     "name" => Ok(ImStr::new("firefox")),
+///     other => Err(anyhow::anyhow!("unknown placeholder {other}")),
+/// });
+/// assert_eq!(rendered.unwrap(), "notify-send firefox {literal}");
+/// assert!(render("{oops", |_| Ok(ImStr::new(""))).is_err());
+/// ```
+pub fn render<F>(template: &str, mut resolve: F) -> anyhow::Result<String>
+where
+    F: FnMut(&str) -> anyhow::Result<ImStr>,
+{
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(brace) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..brace]);
+        let after = &rest[brace + 1..];
+
+        if rest[brace..].starts_with("{{") {
+            rendered.push('{');
+            rest = &after[1..];
+        } else if rest[brace..].starts_with("}}") {
+            rendered.push('}');
+            rest = &after[1..];
+        } else if rest[brace..].starts_with('}') {
+            return Err(anyhow!(
+                "found unmatched `{}` in `{}`; use `{}` for a literal brace",
+                style_stderr!(bold(), "}}"),
+                style_stderr!(bold(), "{template}"),
+                style_stderr!(bold(), "}}}}"),
+            ));
+        } else {
+            let end = after.find('}').ok_or_else(|| {
+                anyhow!(
+                    "found unclosed placeholder in `{}`; use `{}` for a literal brace",
+                    style_stderr!(bold(), "{template}"),
+                    style_stderr!(bold(), "{{{{"),
+                )
+            })?;
+            rendered.push_str(&resolve(&after[..end])?);
+            rest = &after[end + 1..];
+        }
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+/// Replace each `${VAR}` in `value` with the value of the environment variable `VAR`.
+///
+/// Undefined variables expand to an empty string.
+///
+/// ```
+/// use dmm::template::expand_env;
+///
+/// std::env::set_var("DMM_EXAMPLE", "/opt/bin");
+/// assert_eq!(expand_env("${DMM_EXAMPLE}:${DMM_UNDEFINED}"), "/opt/bin:");
+/// ```
+pub fn expand_env(value: &str) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + "${".len()..];
+        if let Some(end) = after.find('}') {
+            expanded.push_str(&rest[..start]);
+            expanded.push_str(&env::var(&after[..end]).unwrap_or_default());
+            rest = &after[end + 1..];
+        } else {
+            break;
+        }
+    }
+    expanded.push_str(rest);
+
+    expanded
+}
+
+/// Replace each `$VAR` and `${VAR}` in `value` with the value of the environment variable `VAR`.
+///
+/// `$$` is an escape for a literal `$`, and a `$` that isn't followed by a variable name,
+/// like in `$1` or `$(date)`, is left as is.
+/// Undefined variables expand to an empty string, or are an error if `strict` is true.
+///
+/// ```
+/// use dmm::template::expand_vars;
+///
+/// std::env::set_var("DMM_EXAMPLE", "/opt");
+/// let expanded = expand_vars("$DMM_EXAMPLE/bin ${DMM_EXAMPLE}x $$HOME $1 $UNDEFINED_DMM", false);
+/// assert_eq!(expanded.unwrap(), "/opt/bin /optx $HOME $1 ");
+/// assert!(expand_vars("$UNDEFINED_DMM", true).is_err());
+/// ```
+pub fn expand_vars(value: &str, strict: bool) -> anyhow::Result<String> {
+    let is_name_start = |c: char| c.is_ascii_alphabetic() || c == '_';
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let (name, next) = if let Some(after) = after.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+            continue;
+        } else if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => {
+                    return Err(anyhow!(
+                        "found unclosed `{}` in `{}`; use `{}` for a literal `$`",
+                        style_stderr!(bold(), "${{"),
+                        style_stderr!(bold(), "{value}"),
+                        style_stderr!(bold(), "$$"),
+                    ))
+                }
+            }
+        } else if after.starts_with(is_name_start) {
+            let end = after.find(|c| !is_name(c)).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        } else {
+            expanded.push('$');
+            rest = after;
+            continue;
+        };
+
+        match env::var(name) {
+            Ok(var) => expanded.push_str(&var),
+            Err(_) if !strict => {}
+            Err(_) => {
+                return Err(anyhow!(
+                    "the environment variable `{}` isn't set",
+                    style_stderr!(bold(), "{name}"),
+                ))
+            }
+        }
+        rest = next;
+    }
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
+/// Split `words` on whitespace, like a shell would.
+///
+/// Single quotes keep everything up to the next single quote literally,
+/// double quotes keep whitespace but allow `\"` and `\\` escapes,
+/// and outside quotes a backslash escapes any character.
+///
+/// ```
+/// use dmm::template::split_words;
+///
+/// let words = split_words(r#"-theme 'my theme' -mesg "say \"hi\"" one\ arg"#).unwrap();
+/// assert_eq!(words, ["-theme", "my theme", "-mesg", "say \"hi\"", "one arg"]);
+/// assert_eq!(split_words("  ''  ").unwrap(), [""]);
+/// assert!(split_words("'unclosed").is_err());
+/// ```
+pub fn split_words(words: &str) -> anyhow::Result<Vec<String>> {
+    let unclosed = |quote: &str| {
+        anyhow!(
+            "found unclosed `{}` in `{}`",
+            style_stderr!(bold(), "{quote}"),
+            style_stderr!(bold(), "{words}"),
+        )
+    };
+
+    let mut split = Vec::new();
+    // `None` between words, so an empty quoted word is still a word.
+    let mut word = None::<String>;
+    let mut chars = words.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => split.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(unclosed("'")),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => word.extend(['\\', c]),
+                            None => return Err(unclosed("\"")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(unclosed("\"")),
+                    }
+                }
+            }
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.next());
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    split.extend(word);
+
+    Ok(split)
+}
@@ -0,0 +1,1998 @@
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{env, fs, process, thread};
+
+/// Write a launcher that runs the shell `script`, returning the path to a pattern that uses it.
+fn fake_launcher(test: &str, script: &str, pattern: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("dmm-test-{}-{test}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let launcher = dir.join("launcher");
+    fs::write(&launcher, format!("#!/bin/sh\n{script}\n")).unwrap();
+    fs::set_permissions(&launcher, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = dir.join("pattern.toml");
+    fs::write(
+        &path,
+        format!("config.launcher = '{}'\n{pattern}", launcher.display()),
+    )
+    .unwrap();
+    path
+}
+
+fn dmm(args: &[&str]) -> Output {
+    dmm_command(args).output().unwrap()
+}
+
+fn dmm_command(args: &[&str]) -> Command {
+    // Keep the user's home config and history out of the tests.
+    let home = env::temp_dir().join(format!("dmm-test-{}-home", process::id()));
+    let mut command = Command::new(env!("CARGO_BIN_EXE_dmm"));
+    command
+        .args(args)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_CACHE_HOME", home.join("cache"));
+    command
+}
+
+#[test]
+fn custom_enables_ad_hoc_commands() {
+    for (custom, selected) in [("true", "echo typed\n"), ("false", "")] {
+        let pattern = fake_launcher(
+            "custom",
+            "cat > /dev/null; echo 'echo typed'",
+            &format!("config.custom = {custom}\n[menu]\nonly = 'echo only'\n"),
+        );
+        let output = dmm(&["--print", pattern.to_str().unwrap()]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            selected,
+            "{custom}: {stderr}"
+        );
+        if custom == "false" {
+            assert!(stderr.contains("ad-hoc commands are disabled"), "{stderr}");
+        }
+    }
+}
+
+#[test]
+fn tag_past_the_end_is_an_error() {
+    let pattern = fake_launcher(
+        "tag_past_the_end",
+        "cat > /dev/null; printf '\u{200c}5\u{200c}: oops\\n'",
+        "config.numbered = true\n[menu]\nonly = 'echo only'\n",
+    );
+    let output = dmm(&["--dry-run", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+    assert!(stderr.contains("past the end of the menu"), "{stderr}");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn numbered_format_places_tag_and_name() {
+    let pattern = fake_launcher(
+        "numbered_format",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 2p",
+        "config.numbered = { numbered = true, format = '[{tag}] {name} <' }\n\
+         [menu]\nfirst = 'echo 1'\nsecond = 'echo 2'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo 2\n",
+        "{stderr}"
+    );
+
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu.replace('\u{200c}', ""), "[0] first <\n[1] second <\n");
+}
+
+#[test]
+fn hidden_tags_select_by_name() {
+    let pattern = fake_launcher(
+        "hidden_tags",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 2p",
+        "config.show-tags = false\n[menu]\nfirst = 'echo 1'\nsecond = 'echo 2'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo 2\n",
+        "{stderr}"
+    );
+
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "first\nsecond\n");
+}
+
+#[test]
+fn hidden_tags_reject_duplicate_names() {
+    let pattern = fake_launcher(
+        "hidden_tags_duplicates",
+        "cat",
+        "config.show-tags = false\n\
+         [menu.power]\nsubmenu = [{ name = 'off', run = 'a' }, { name = 'off', run = 'b' }]\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("named `off`"), "{stderr}");
+    assert!(
+        stderr.contains("entries 0 and 1 of `menu.power.submenu`"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn check_finds_duplicate_commands() {
+    let pattern = fake_launcher(
+        "duplicate_commands",
+        "cat",
+        "[menu]
+top = 'htop'
+processes = { run = 'htop', group = 1 }
+other = 'btop'
+",
+    );
+    let output = dmm(&["--check", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("`menu.top` runs the same command as `menu.processes`"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("menu.other"), "{stderr}");
+}
+
+#[test]
+fn icons_are_passed_when_enabled() {
+    let pattern = fake_launcher(
+        "icons",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 1p",
+        "config.icons = true\nconfig.show-tags = false\n\
+         [menu]\nfiles = { run = 'thunar', icon = 'system-file-manager' }\nplain = 'echo'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "files\0icon\x1fsystem-file-manager\nplain\n");
+}
+
+#[test]
+fn cancelled_launcher_is_not_an_error() {
+    let pattern = fake_launcher(
+        "cancelled",
+        "cat > /dev/null; exit 1",
+        "[menu]\nonly = 'echo only'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn launcher_can_exit_before_reading_the_whole_menu() {
+    let entries = (0..20_000)
+        .map(|i| format!("entry-{i} = 'echo {i}'\n"))
+        .collect::<String>();
+    let pattern = fake_launcher(
+        "early_exit",
+        "head -n 1",
+        &format!("config.show-tags = false\n[menu]\n{entries}"),
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "echo 0\n");
+
+    // Closing stdin before reading anything makes every write to it fail.
+    let launcher = pattern.with_file_name("launcher");
+    fs::write(&launcher, "#!/bin/sh\nexec 0<&-\necho entry-7\n").unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "echo 7\n");
+}
+
+#[test]
+fn cancelling_exits_with_exit_on_cancel() {
+    let pattern = fake_launcher(
+        "exit_on_cancel",
+        "cat > /dev/null",
+        "config.exit-on-cancel = 5\n[menu]\nonly = 'echo only'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(5), "{stderr}");
+}
+
+#[test]
+fn sticky_entries_show_the_menu_again_until_cancelled() {
+    // Select the first entry twice, then cancel.
+    let script = r#"n=$(cat "$0.count" 2>/dev/null || echo 0)
+echo $((n + 1)) > "$0.count"
+if [ "$n" -lt 2 ]; then sed -n 1p; else cat > /dev/null; exit 1; fi"#;
+    let pattern = fake_launcher(
+        "sticky",
+        script,
+        "config.wait = true\nconfig.exit-on-cancel = 5\n\
+         [menu]\nlouder = { run = 'echo louder', sticky = true }\n",
+    );
+    let _ = fs::remove_file(pattern.with_file_name("launcher.count"));
+    let output = dmm_command(&[pattern.to_str().unwrap()])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(5), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "louder\nlouder\n");
+
+    // Printed commands aren't run, so the menu isn't shown again.
+    fs::remove_file(pattern.with_file_name("launcher.count")).unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "echo louder\n");
+}
+
+#[test]
+fn unknown_keys_are_errors() {
+    let pattern = fake_launcher(
+        "unknown_keys",
+        "cat",
+        "config.numbred = true
+config.dmenu.fnot = 'monospace'
+
+[menu]
+htop = { run = 'htop', grup = 1 }
+",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    for key in ["config.numbred", "config.dmenu.fnot", "menu.htop.grup"] {
+        assert!(
+            stderr.contains(&format!("`{key}` is not a known key")),
+            "{stderr}"
+        );
+    }
+}
+
+#[test]
+fn urls_are_opened_with_the_opener() {
+    let pattern = fake_launcher(
+        "url",
+        "sed -n 1p",
+        "[menu]\ndocs = { url = 'https://doc.rust-lang.org/std/' }\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "xdg-open https://doc.rust-lang.org/std/\n",
+        "{stderr}"
+    );
+
+    let config = fs::read_to_string(&pattern).unwrap();
+    fs::write(
+        &pattern,
+        format!("config.opener = \"firefox --new-tab\"\n{config}"),
+    )
+    .unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "firefox --new-tab https://doc.rust-lang.org/std/\n"
+    );
+
+    fs::write(
+        &pattern,
+        format!("{config}both = {{ url = 'https://example.com', run = 'true' }}\n"),
+    )
+    .unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("`menu.both.run` and `menu.both.url` can't both have a value"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn empty_commands_are_errors() {
+    for (test, entry, key) in [
+        ("empty_run", "blank = '  '", "menu.blank"),
+        ("empty_args", "nothing = { args = [] }", "menu.nothing"),
+        (
+            "empty_step",
+            "power.submenu.off = { steps = ['sync', ''] }",
+            "menu.power.submenu.off",
+        ),
+    ] {
+        let pattern = fake_launcher(test, "sed -n 1p", &format!("[menu]\n{entry}\n"));
+        let output = dmm(&["--print", pattern.to_str().unwrap()]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(!output.status.success(), "{stderr}");
+        assert!(
+            stderr.contains(&format!("`{key}` has an empty command")),
+            "{stderr}"
+        );
+    }
+}
+
+#[test]
+fn input_method_file_gives_the_launcher_a_temporary_file() {
+    let pattern = fake_launcher(
+        "input_method",
+        "dir=\"$(dirname \"$0\")\"\n\
+         if [ -f /dev/stdin ]; then echo file > \"$dir/kind\"; else echo pipe > \"$dir/kind\"; fi\n\
+         readlink -f /dev/stdin > \"$dir/stdin\"\n\
+         sed -n 1p",
+        "[menu]\nhtop = 'htop'\n",
+    );
+    let kind = pattern.with_file_name("kind");
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&kind).unwrap(), "pipe\n");
+
+    let config = fs::read_to_string(&pattern).unwrap();
+    fs::write(&pattern, format!("config.input-method = 'file'\n{config}")).unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "htop\n",
+        "{stderr}"
+    );
+    assert_eq!(fs::read_to_string(&kind).unwrap(), "file\n");
+    let menu_file = fs::read_to_string(pattern.with_file_name("stdin")).unwrap();
+    assert!(menu_file.contains("dmm-menu-"), "{menu_file}");
+    assert!(!Path::new(menu_file.trim_end()).exists(), "{menu_file}");
+}
+
+#[test]
+fn toml_errors_show_where_they_are() {
+    let mut child = dmm_command(&["--print"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"[menu]\nhtop = 'htop'\nbtop =\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("found incorrect formatting in `<stdin>`"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("line 3 is `btop =`"), "{stderr}");
+    assert!(stderr.contains("at line 3 column"), "{stderr}");
+
+    let pattern = fake_launcher("toml_errors", "sed -n 1p", "[menu\n");
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "found incorrect formatting in `{}`",
+            pattern.display()
+        )),
+        "{stderr}"
+    );
+    assert!(stderr.contains("line 2 is `[menu`"), "{stderr}");
+}
+
+#[test]
+fn failed_launcher_shows_its_errors() {
+    let pattern = fake_launcher(
+        "failed",
+        "cat > /dev/null; echo 'cannot open display' >&2; exit 1",
+        "[menu]\nonly = 'echo only'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("cannot open display"), "{stderr}");
+
+    // A launcher that succeeds may still explain a problem, which is logged.
+    let pattern = fake_launcher(
+        "failed_warning",
+        "sed -n 1p; echo 'missing font' >&2",
+        "config.show-tags = false\n[menu]\nonly = 'echo only'\n",
+    );
+    let output = dmm(&["-v", "--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(
+        stderr.contains("info: the launcher printed errors \"missing font\\n\""),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn dumped_config_loads_the_same() {
+    let pattern = fake_launcher(
+        "dump_config",
+        "exit 1",
+        "config.source = { source = 'echo sourced', run = 'echo {name}' }\n\
+         [menu]\nplain = 'echo'\nbare = { args = ['ls', '-l'], group = 2 }\n\
+         secret = { run = 'echo secret', hidden = true }\n\
+         never = { run = 'echo never', when = 'false' }\n\
+         tools = { submenu = { inner = { run = 'echo inner', hidden = true, when = 'false' } } }\n",
+    );
+    let output = dmm(&["--dump-config", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+
+    let dumped = String::from_utf8(output.stdout).unwrap();
+    assert!(dumped.contains("[menu.sourced]"), "{dumped}");
+    assert!(dumped.contains("group = 2"), "{dumped}");
+    // Hidden entries, and those a `when` command would leave out, are part of the config.
+    assert!(dumped.contains("[menu.secret]\nhidden = true"), "{dumped}");
+    assert!(dumped.contains("[menu.never]"), "{dumped}");
+    assert!(dumped.contains("when = 'false'"), "{dumped}");
+    assert!(dumped.contains("[menu.tools.submenu.inner]"), "{dumped}");
+
+    let path = pattern.with_file_name("dumped.toml");
+    fs::write(&path, &dumped).unwrap();
+    let output = dmm(&["--dump-config", path.to_str().unwrap()]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), dumped);
+}
+
+#[test]
+fn patterns_are_merged_and_later_ones_take_precedence() {
+    let pattern = fake_launcher(
+        "several_patterns",
+        "echo \"$@\" > \"$(dirname \"$0\")/args\"; tee \"$(dirname \"$0\")/menu\" | grep shared",
+        "config.prompt = 'base'\n[menu]\nshared = 'echo base'\nbase-only = 'echo base-only'\n",
+    );
+    let extra = pattern.with_file_name("extra.toml");
+    fs::write(
+        &extra,
+        "config.prompt = 'extra'\n[menu]\nshared = 'echo extra'\nextra-only = 'echo extra-only'\n",
+    )
+    .unwrap();
+    let output = dmm(&[
+        "--print",
+        pattern.to_str().unwrap(),
+        extra.to_str().unwrap(),
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo extra\n",
+        "{stderr}"
+    );
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    let names = menu
+        .lines()
+        .map(|line| line.trim_end_matches(['\u{200b}', '\u{200c}', '\u{200d}']))
+        .collect::<Vec<&str>>();
+    assert_eq!(names, ["base-only", "extra-only", "shared"]);
+    let args = fs::read_to_string(pattern.with_file_name("args")).unwrap();
+    assert!(args.contains("-p extra"), "{args}");
+
+    let missing = pattern.with_file_name("missing.toml");
+    let output = dmm(&[
+        "--print",
+        pattern.to_str().unwrap(),
+        missing.to_str().unwrap(),
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains(&format!(
+            "unable to read config file `{}`",
+            missing.display()
+        )),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn empty_separator_joins_tag_and_name() {
+    let pattern = fake_launcher(
+        "empty_separator",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 2p",
+        "config.numbered = { numbered = true, separator = '' }\n\
+         [menu]\nfirst = 'echo 1'\nsecond = 'echo 2'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo 2\n",
+        "{stderr}"
+    );
+
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu.replace('\u{200c}', ""), "0first\n1second\n");
+}
+
+#[test]
+fn separator_is_the_same_with_every_tag_type() {
+    for (tags, second) in [("decimal", "1"), ("alpha", "b"), ("hex", "1")] {
+        for (numbered, separator) in [
+            ("true", ": "),
+            ("{ numbered = true, separator = ' | ' }", " | "),
+            ("{ numbered = true, separator = '' }", ""),
+        ] {
+            let pattern = fake_launcher(
+                &format!("separator_{tags}"),
+                "tee \"$(dirname \"$0\")/menu\" | sed -n 2p",
+                &format!(
+                    "config.tags = '{tags}'\nconfig.numbered = {numbered}\n\
+                     [menu]\nfirst = 'echo 1'\nsecond = 'echo 2'\n"
+                ),
+            );
+            let output = dmm(&["--print", pattern.to_str().unwrap()]);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert_eq!(
+                String::from_utf8_lossy(&output.stdout),
+                "echo 2\n",
+                "{tags} {numbered}: {stderr}"
+            );
+
+            let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+            let first = if tags == "alpha" { "a" } else { "0" };
+            assert_eq!(
+                menu.replace('\u{200c}', ""),
+                format!("{first}{separator}first\n{second}{separator}second\n"),
+                "{tags} {numbered}"
+            );
+        }
+    }
+}
+
+#[test]
+fn long_separator_is_shown_and_ignored_when_matching() {
+    let pattern = fake_launcher(
+        "long_separator",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 1p",
+        "config.numbered = { numbered = true, separator = ' \u{2014} ' }\n\
+         [menu]\nfirst = 'echo 1'\n'a \u{2014} b' = 'echo 2'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo 2\n",
+        "{stderr}"
+    );
+
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(
+        menu.replace('\u{200c}', ""),
+        "0 \u{2014} a \u{2014} b\n1 \u{2014} first\n"
+    );
+
+    fs::write(
+        &pattern,
+        "config.numbered = { numbered = true, separator = \"\\n\" }\n",
+    )
+    .unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("can't contain a newline"), "{stderr}");
+}
+
+#[test]
+fn descriptions_are_aligned_and_ignored_when_matching() {
+    let pattern = fake_launcher(
+        "descriptions",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 1p",
+        "config.show-tags = false\n[menu]\n\
+         firefox = { run = 'echo firefox', description = 'Web browser' }\n\
+         mpv = { run = 'echo mpv', description = 'Video player' }\nplain = 'echo plain'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo firefox\n",
+        "{stderr}"
+    );
+
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "firefox  Web browser\nmpv      Video player\nplain\n");
+}
+
+#[test]
+fn invalid_utf8_from_the_launcher() {
+    let script = "cat > /dev/null; printf 'ab\\377\\n'";
+    let menu = "[menu]\nonly = 'echo only'\n";
+
+    let pattern = fake_launcher("invalid_utf8", script, menu);
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("invalid utf-8 at byte 2"), "{stderr}");
+
+    let pattern = fake_launcher(
+        "invalid_utf8_lossy",
+        script,
+        &format!("config.lossy-input = true\nconfig.custom = true\n{menu}"),
+    );
+    let output = dmm(&["--dry-run", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("ab\u{fffd}"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn post_runs_after_a_failed_command() {
+    let pattern = fake_launcher(
+        "hooks",
+        "sed -n 1p",
+        "config.wait = true\nconfig.shell = ['sh', '-c']\n\
+         config.pre = 'echo pre >> \"$DMM_TEST_LOG\"'\n\
+         config.post = 'echo post >> \"$DMM_TEST_LOG\"'\n\
+         [menu]\nfail = 'echo run >> \"$DMM_TEST_LOG\"; exit 3'\n",
+    );
+    let log = pattern.with_file_name("log");
+    let output = dmm_command(&[pattern.to_str().unwrap()])
+        .env("DMM_TEST_LOG", &log)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(3), "{stderr}");
+    assert_eq!(fs::read_to_string(log).unwrap(), "pre\nrun\npost\n");
+}
+
+#[test]
+fn failed_pre_stops_the_menu() {
+    let pattern = fake_launcher(
+        "failed_pre",
+        "touch \"$(dirname \"$0\")/shown\"; sed -n 1p",
+        "config.pre = ['false']\n[menu]\nonly = 'echo only'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("config.pre"), "{stderr}");
+    assert!(!pattern.with_file_name("shown").exists());
+}
+
+#[test]
+fn shell_args_replace_the_shells_arguments() {
+    let pattern = fake_launcher("shell_args", "sed -n 1p", "[menu]\nhello = 'echo hello'\n");
+    let shell = pattern.with_file_name("shell");
+    fs::write(
+        &shell,
+        "#!/bin/sh\necho \"$@\" > \"$(dirname \"$0\")/args\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&shell, fs::Permissions::from_mode(0o755)).unwrap();
+    let config = fs::read_to_string(&pattern).unwrap();
+    fs::write(
+        &pattern,
+        format!(
+            "config.shell = ['{}', '-c']\nconfig.shell-args = '--eval --quiet'\n{config}",
+            shell.display()
+        ),
+    )
+    .unwrap();
+
+    let output = dmm(&[pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    let args = fs::read_to_string(pattern.with_file_name("args")).unwrap();
+    assert_eq!(args, "--eval --quiet echo hello\n");
+}
+
+#[test]
+fn env_is_expanded_when_loading() {
+    let pattern = fake_launcher(
+        "expand_env",
+        "exit 1",
+        "config.expand-env = { expand-env = true, strict = true }\n\
+         [menu]\nthing = { run = '$DMM_TEST_DIR/thing $$1', cwd = '${DMM_TEST_DIR}' }\n",
+    );
+    let output = dmm_command(&["--dump-config", pattern.to_str().unwrap()])
+        .env("DMM_TEST_DIR", "/opt")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let dumped = String::from_utf8_lossy(&output.stdout);
+
+    assert!(dumped.contains("run = '/opt/thing $1'"), "{stderr}{dumped}");
+    assert!(dumped.contains("cwd = '/opt'"), "{dumped}");
+
+    let output = dmm(&["--dump-config", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("`DMM_TEST_DIR` isn't set"), "{stderr}");
+}
+
+#[test]
+fn escaped_env_values_are_not_expanded_again_when_running() {
+    let pattern = fake_launcher(
+        "expand_env_escaped",
+        "sed -n 1p",
+        "config.expand-env = true\nconfig.shell = ['sh', '-c']\nconfig.wait = true\n\
+         [menu]\nthing = { run = 'printf %s \"$$VALUE\"', env = { VALUE = '$${DMM_TEST_DIR}' } }\n",
+    );
+    let output = dmm_command(&[pattern.to_str().unwrap()])
+        .env("DMM_TEST_DIR", "/opt")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "${DMM_TEST_DIR}");
+}
+
+#[test]
+fn missing_shell_is_an_error_when_loading() {
+    let pattern = fake_launcher(
+        "missing_shell",
+        "touch \"$(dirname \"$0\")/shown\"; sed -n 1p",
+        "config.shell = ['/nonexistent/dash', '-c']\n[menu]\nonly = 'echo only'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("/nonexistent/dash"), "{stderr}");
+    assert!(!pattern.with_file_name("shown").exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn detached_commands_get_their_own_process_group() {
+    let pgid = "cut -d' ' -f5 /proc/$$/stat; cut -d' ' -f5 /proc/$PPID/stat";
+    let pattern = fake_launcher(
+        "detach",
+        "sed -n 1p",
+        &format!(
+            "config.detach = true\nconfig.wait = true\nconfig.shell = ['sh', '-c']\n\
+             [menu]\npgid = \"{pgid}\"\n"
+        ),
+    );
+    let output = dmm(&[pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let pgids = stdout.lines().collect::<Vec<&str>>();
+    assert_eq!(pgids.len(), 2, "{stderr}");
+    assert_ne!(pgids[0], pgids[1]);
+}
+
+#[test]
+fn ad_hoc_commands_can_run_without_a_shell() {
+    let script = r#"cat > /dev/null; printf '%s\n' "echo 'a b' \$HOME""#;
+    let ad_hoc = |test: &str, shell: bool| {
+        let pattern = fake_launcher(
+            test,
+            script,
+            &format!(
+                "config.custom = true\nconfig.wait = true\nconfig.shell = ['sh', '-c']\n\
+                 config.ad-hoc-shell = {shell}\n\
+                 [menu]\nonly = 'echo only'\n"
+            ),
+        );
+        let output = dmm_command(&[pattern.to_str().unwrap()])
+            .env("HOME", "/home")
+            .stdin(Stdio::null())
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{stderr}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(ad_hoc("ad_hoc_shell", true), "a b /home\n");
+    assert_eq!(ad_hoc("ad_hoc_no_shell", false), "a b $HOME\n");
+}
+
+#[test]
+fn editable_commands_are_edited_before_running() {
+    let edited = |test: &str, edit: &str| {
+        let script = format!("case \"$*\" in *'Edit only:'*) {edit} ;; *) sed -n 1p ;; esac");
+        let pattern = fake_launcher(
+            test,
+            &script,
+            "config.editable = true\nconfig.wait = true\nconfig.shell = ['sh', '-c']\n\
+             [menu]\nonly = 'echo only'\n",
+        );
+        let output = dmm_command(&[pattern.to_str().unwrap()])
+            .stdin(Stdio::null())
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{stderr}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        edited("editable", r#"read -r command; echo "$command edited""#),
+        "only edited\n"
+    );
+    assert_eq!(edited("editable_unchanged", "cat"), "only\n");
+    assert_eq!(edited("editable_cancelled", "cat > /dev/null; exit 1"), "");
+}
+
+#[test]
+fn confirm_ad_hoc_asks_before_running_typed_commands() {
+    let run = |test: &str, choice: &str, answer: &str| {
+        let script = format!(
+            "echo \"$*\" >> \"$(dirname \"$0\")/prompts\"\n\
+             case \"$*\" in *'Run '*) echo {answer} ;; *) echo '{choice}' ;; esac"
+        );
+        let pattern = fake_launcher(
+            test,
+            &script,
+            "config.custom = true\nconfig.confirm-ad-hoc = true\nconfig.wait = true\n\
+             config.shell = ['sh', '-c']\n[menu]\nonly = 'echo only'\n",
+        );
+        let _ = fs::remove_file(pattern.with_file_name("prompts"));
+        let output = dmm_command(&[pattern.to_str().unwrap()])
+            .stdin(Stdio::null())
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{stderr}");
+        let prompts = fs::read_to_string(pattern.with_file_name("prompts")).unwrap();
+        (
+            String::from_utf8(output.stdout).unwrap(),
+            prompts.lines().count(),
+        )
+    };
+
+    assert_eq!(
+        run("confirm_ad_hoc", "echo typed", "yes"),
+        ("typed\n".to_owned(), 2)
+    );
+    assert_eq!(
+        run("confirm_ad_hoc_declined", "echo typed", "no"),
+        (String::new(), 2)
+    );
+    assert_eq!(
+        run("confirm_ad_hoc_entry", "only", "no"),
+        ("only\n".to_owned(), 1)
+    );
+}
+
+#[test]
+fn typed_entry_names_select_the_entry_instead_of_an_ad_hoc_command() {
+    for tags in [
+        "config.numbered = true",
+        "config.numbered = false",
+        "config.show-tags = false",
+    ] {
+        let typed = |typed: &str| {
+            let pattern = fake_launcher(
+                "typed_entry_name",
+                &format!("cat > /dev/null; echo '{typed}'"),
+                &format!("config.custom = true\n{tags}\n[menu]\nfirefox = 'echo entry'\n"),
+            );
+            let output = dmm(&["--print", pattern.to_str().unwrap()]);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(output.status.success(), "{tags}: {stderr}");
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        assert_eq!(typed("firefox"), "echo entry\n", "{tags}");
+        assert_eq!(typed("firefox --private"), "firefox --private\n", "{tags}");
+    }
+}
+
+#[test]
+fn typed_tags_select_their_entry() {
+    let typed = |config: &str, typed: &str| {
+        let pattern = fake_launcher(
+            "typed_tag",
+            &format!("cat > /dev/null; echo '{typed}'"),
+            &format!(
+                "config.custom = true\n{config}\n\
+                 [menu]\nfirst = 'echo first'\nsecond = 'echo second'\n"
+            ),
+        );
+        let output = dmm(&["--print", pattern.to_str().unwrap()]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{config}: {stderr}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let alpha = "config.numbered = true\nconfig.tags = 'alpha'";
+    assert_eq!(typed(alpha, "b"), "echo second\n");
+    assert_eq!(typed(alpha, "B"), "B\n");
+    let insensitive = format!("{alpha}\nconfig.case-insensitive = true");
+    assert_eq!(typed(&insensitive, "B"), "echo second\n");
+
+    let padded = "config.numbered = true\nconfig.tag-padding = true";
+    assert_eq!(typed(padded, "1"), "echo second\n");
+    // Past the end of the menu, or without visible tags, it's an ad-hoc command.
+    assert_eq!(typed(padded, "2"), "2\n");
+    assert_eq!(typed("config.numbered = false", "1"), "1\n");
+
+    // An alias is matched before a typed tag.
+    let pattern = fake_launcher(
+        "typed_tag_alias",
+        "cat > /dev/null; echo b",
+        "config.numbered = true\nconfig.tags = 'alpha'\n\
+         [menu]\nalpha = 'echo alpha'\nbeta = 'echo beta'\n\
+         web = { run = 'echo firefox', aliases = ['b'] }\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo firefox\n",
+        "{stderr}"
+    );
+}
+
+#[test]
+fn values_are_substituted_with_their_commands_output() {
+    let pattern = fake_launcher(
+        "values",
+        "sed -n 1p",
+        r#"config.shell = ['sh', '-c']
+[menu]
+vpn = { run = 'echo {token} {token}', values = { token = 'echo run >> "$DMM_TEST_LOG"; printf " secret\n"' } }
+"#,
+    );
+    let log = pattern.with_file_name("log");
+    let _ = fs::remove_file(&log);
+    let output = dmm_command(&["--print", pattern.to_str().unwrap()])
+        .env("DMM_TEST_LOG", &log)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo secret secret\n",
+        "{stderr}"
+    );
+    // A value used twice is only produced once.
+    assert_eq!(fs::read_to_string(&log).unwrap(), "run\n");
+
+    let pattern = fake_launcher(
+        "values_failed",
+        "sed -n 1p",
+        "[menu]\nvpn = { run = 'echo {token}', values = { token = ['false'] } }\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.stdout.is_empty(), "{stderr}");
+    assert!(
+        stderr.contains("unable to get the value of `{token}`"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn ad_hoc_prefix_is_required_for_ad_hoc_commands() {
+    let typed = |test: &str, typed: &str| {
+        let pattern = fake_launcher(
+            test,
+            &format!("cat > /dev/null; echo '{typed}'"),
+            "config.custom = true\nconfig.ad-hoc-prefix = '$ '\n[menu]\nhtop = 'echo htop'\n",
+        );
+        dmm(&["--print", pattern.to_str().unwrap()])
+    };
+
+    let output = typed("ad_hoc_prefix", "$ echo typed");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "echo typed\n");
+
+    let output = typed("ad_hoc_prefix_entry", "htop");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "echo htop\n");
+
+    let output = typed("ad_hoc_prefix_missing", "htpo");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.stdout.is_empty(), "{stderr}");
+    assert!(
+        stderr.contains("ad-hoc commands must start with"),
+        "{stderr}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_replaces_dmm_with_the_only_selected_command() {
+    let pid = |test: &str, exec: bool| {
+        let pattern = fake_launcher(
+            test,
+            "sed -n 1p",
+            &format!(
+                "config.exec = {exec}\nconfig.wait = true\nconfig.shell = ['sh', '-c']\n\
+                 [menu]\npid = 'echo $$'\n"
+            ),
+        );
+        let child = dmm_command(&[pattern.to_str().unwrap()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let dmm = child.id();
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{stderr}");
+        let pid = String::from_utf8(output.stdout).unwrap();
+        (dmm, pid.trim().parse::<u32>().unwrap())
+    };
+
+    let (dmm, command) = pid("exec", true);
+    assert_eq!(dmm, command);
+    let (dmm, command) = pid("exec_disabled", false);
+    assert_ne!(dmm, command);
+}
+
+#[test]
+fn aliases_select_their_entry_without_being_shown() {
+    let typed = |test: &str, typed: &str, config: &str| {
+        let pattern = fake_launcher(
+            test,
+            &format!("tee \"$(dirname \"$0\")/menu\" > /dev/null; echo '{typed}'"),
+            &format!(
+                "config.custom = true\nconfig.show-tags = false\n{config}\n[menu]\n\
+                 firefox = {{ run = 'echo firefox', aliases = ['web', 'ff'] }}\n"
+            ),
+        );
+        let output = dmm(&["--print", pattern.to_str().unwrap()]);
+        let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+        assert_eq!(menu, "firefox\n");
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(typed("aliases", " ff ", ""), "echo firefox\n");
+    assert_eq!(typed("aliases_case", "WEB", ""), "WEB\n");
+    assert_eq!(
+        typed(
+            "aliases_insensitive",
+            "WEB",
+            "config.case-insensitive = true"
+        ),
+        "echo firefox\n"
+    );
+}
+
+#[test]
+fn fuzzy_text_selects_the_only_match() {
+    let menu = "config.fuzzy = true\nconfig.custom = true\n\
+                [menu]\nfirefox = 'echo firefox'\nfiles = 'echo files'\nmpv = 'echo mpv'\n";
+    let selected = |test: &str, typed: &str| {
+        let script = format!("cat > /dev/null; echo '{typed}'");
+        let pattern = fake_launcher(test, &script, menu);
+        let output = dmm(&["--print", pattern.to_str().unwrap()]);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(selected("fuzzy_only", "frfx"), "echo firefox\n");
+    assert_eq!(selected("fuzzy_prefix", "FIL"), "echo files\n");
+    assert_eq!(selected("fuzzy_ambiguous", "fi"), "fi\n");
+}
+
+#[test]
+fn extra_launcher_args_are_split_like_a_shell() {
+    let pattern = fake_launcher(
+        "dmenu_extra",
+        "printf '%s\\n' \"$@\" > \"$(dirname \"$0\")/args\"; cat > /dev/null",
+        "config.dmenu = { lines = 3, extra = \"-theme 'my theme' -x\" }\n\
+         [menu]\nonly = 'echo only'\n",
+    );
+    // `config.dmenu` is only passed to dmenu.
+    let dmenu = pattern.with_file_name("dmenu");
+    fs::copy(pattern.with_file_name("launcher"), &dmenu).unwrap();
+    let config = fs::read_to_string(&pattern).unwrap();
+    fs::write(&pattern, config.replace("/launcher'", "/dmenu'")).unwrap();
+    dmm(&["--print", pattern.to_str().unwrap(), "--", "-cli"]);
+
+    let args = fs::read_to_string(pattern.with_file_name("args")).unwrap();
+    assert_eq!(args, "-i\n-l\n3\n-theme\nmy theme\n-x\n-cli\n");
+}
+
+#[test]
+fn categories_have_headers_that_do_nothing() {
+    let pattern = fake_launcher(
+        "categories",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 2p",
+        "config.show-tags = false\nconfig.custom = true\n[menu]\n\
+         mpv = { run = 'echo mpv', category = 'Media' }\n\
+         firefox = { run = 'echo firefox', category = 'Web', group = 1 }\n\
+         vlc = { run = 'echo vlc', category = 'Media' }\nplain = 'echo plain'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(output.stdout.is_empty(), "{stderr}");
+
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "plain\n-- Web --\nfirefox\n-- Media --\nmpv\nvlc\n");
+}
+
+#[test]
+fn menu_flag_selects_from_menus() {
+    let pattern = fake_launcher(
+        "menus",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 1p",
+        "config.show-tags = false\n\
+         [menus.default]\nfirefox = 'firefox'\n\
+         [menus.power]\nreboot = 'reboot'\nshutdown = 'poweroff'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "firefox\n");
+
+    let output = dmm(&["--print", "--menu", "power", pattern.to_str().unwrap()]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "reboot\n");
+
+    let output = dmm(&["--print", "--menu", "media", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr
+            .contains("there's no menu named `media`; the available menus are `default`, `power`"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn steps_stop_at_the_first_failure() {
+    let steps =
+        "steps = ['echo one >> \"$DMM_TEST_LOG\"', 'exit 4', 'echo three >> \"$DMM_TEST_LOG\"']";
+    for (test, ignore_errors, code, log_contents) in [
+        ("steps", false, 1, "one\n"),
+        ("steps_ignore_errors", true, 0, "one\nthree\n"),
+    ] {
+        let pattern = fake_launcher(
+            test,
+            "sed -n 1p",
+            &format!(
+                "config.wait = true\nconfig.shell = ['sh', '-c']\n\
+                 [menu]\nchain = {{ {steps}, ignore-errors = {ignore_errors} }}\n"
+            ),
+        );
+        let log = pattern.with_file_name("log");
+        let _ = fs::remove_file(&log);
+        let output = dmm_command(&[pattern.to_str().unwrap()])
+            .env("DMM_TEST_LOG", &log)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(output.status.code(), Some(code), "{stderr}");
+        assert!(
+            stderr.contains("problem running step 2 of `chain`"),
+            "{stderr}"
+        );
+        assert_eq!(fs::read_to_string(log).unwrap(), log_contents);
+    }
+}
+
+#[test]
+fn verbose_logs_the_launcher_and_commands() {
+    let pattern = fake_launcher(
+        "verbose",
+        "sed -n 1p",
+        "config.show-tags = false\nconfig.wait = true\n[menu]\nhello = ['true']\n",
+    );
+
+    let output = dmm(&["-v", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(stderr.contains("info: running launcher"), "{stderr}");
+    assert!(
+        stderr.contains("info: the launcher output \"hello\\n\""),
+        "{stderr}"
+    );
+    assert!(stderr.contains("info: running \"true\""), "{stderr}");
+    assert!(!stderr.contains("debug:"), "{stderr}");
+
+    let output = dmm(&["-vv", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("debug: menu line \"hello\""), "{stderr}");
+    assert!(
+        stderr.contains("debug: the launcher exited after"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn capture_logs_the_output_of_commands() {
+    let pattern = fake_launcher(
+        "capture",
+        "sed -n 1p",
+        "config.show-tags = false\nconfig.capture = true\nconfig.shell = ['sh', '-c']\n\
+         [menu]\nnoisy = 'echo out; echo err >&2; seq 30'\n",
+    );
+
+    let output = dmm(&["-v", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(output.stdout.is_empty(), "{stderr}");
+    assert!(stderr.contains("info: `noisy` stdout: out\n"), "{stderr}");
+    assert!(stderr.contains("info: `noisy` stderr: err\n"), "{stderr}");
+    assert!(stderr.contains("info: `noisy` stdout: 19\n"), "{stderr}");
+    assert!(!stderr.contains("info: `noisy` stdout: 20\n"), "{stderr}");
+    assert!(
+        stderr.contains("info: `noisy` stdout: (11 more lines)"),
+        "{stderr}"
+    );
+
+    let output = dmm(&[pattern.to_str().unwrap()]);
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn when_commands_decide_which_entries_are_shown() {
+    let pattern = fake_launcher(
+        "when",
+        "tee \"$(dirname \"$0\")/menu\" > /dev/null",
+        "config.show-tags = false\nconfig.shell = ['sh', '-c']\n[menu]\n\
+         always = 'true'\n\
+         up = { run = 'true', when = 'exit 0' }\n\
+         down = { run = 'true', when = ['false'] }\n\
+         hung = { run = 'true', when = 'sleep 5' }\n",
+    );
+    let output = dmm(&[pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+    assert!(
+        stderr.contains("the `when` command of `hung` didn't finish within 1000 ms"),
+        "{stderr}"
+    );
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "always\nup\n");
+}
+
+#[test]
+fn schedules_decide_when_entries_are_shown() {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let hour = seconds % 86_400 / 3600;
+    let hours = |from: u64, to: u64| format!("{:02}:00-{:02}:00", from % 24, to % 24);
+    // Wide ranges, so the hour changing while the test runs doesn't matter.
+    let (now, later) = (hours(hour + 23, hour + 2), hours(hour + 6, hour + 8));
+
+    let pattern = fake_launcher(
+        "schedule",
+        "tee \"$(dirname \"$0\")/menu\" > /dev/null",
+        &format!(
+            "config.show-tags = false\n[menu]\n\
+             always = 'true'\n\
+             now = {{ run = 'true', schedule = 'Mon-Sun {now}' }}\n\
+             later = {{ run = 'true', schedule = '{later}' }}\n\
+             either = {{ run = 'true', schedule = ['{later}', '{now}'] }}\n"
+        ),
+    );
+    let output = dmm_command(&[pattern.to_str().unwrap()])
+        .env("TZ", "UTC")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "always\neither\nnow\n");
+
+    fs::write(
+        &pattern,
+        "[menu]\nbad = { run = 'true', schedule = 'Mon-Someday' }\n",
+    )
+    .unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("`Someday` isn't a day"), "{stderr}");
+}
+
+#[test]
+fn piped_config_is_merged_into_the_pattern() {
+    let pattern = fake_launcher(
+        "merge_piped",
+        "tee \"$(dirname \"$0\")/menu\" > /dev/null",
+        "config.show-tags = false\n[menu]\nbase = 'true'\nshared = 'echo base'\n",
+    );
+    let mut child = dmm_command(&["--dump-config", pattern.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"config.show-tags = true\n[menu]\ndynamic = 'true'\nshared = 'echo piped'\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let dumped = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{dumped}");
+    assert!(dumped.contains("[menu.base]"), "{dumped}");
+    assert!(dumped.contains("[menu.dynamic]"), "{dumped}");
+    assert!(dumped.contains("run = 'echo base'"), "{dumped}");
+    assert!(!dumped.contains("echo piped"), "{dumped}");
+    assert!(dumped.contains("show-tags = false"), "{dumped}");
+}
+
+#[test]
+fn key_hints_are_shown_before_names() {
+    let pattern = fake_launcher(
+        "key_hints",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 1p",
+        "config.show-tags = false\n[menu]\n\
+         quit = { run = 'echo quit', key = 'q', description = 'Leave' }\n\
+         reload = { run = 'echo reload', description = 'Again' }\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "echo quit\n");
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "[q] quit  Leave\nreload    Again\n");
+}
+
+#[test]
+fn directory_pattern_reads_each_toml_file() {
+    let pattern = fake_launcher("config_dir", "cat > /dev/null", "");
+    let dir = pattern.with_file_name("menu.d");
+    fs::create_dir_all(&dir).unwrap();
+    let launcher = fs::read_to_string(&pattern).unwrap();
+    fs::write(
+        dir.join("20-b.toml"),
+        "[menu]\nb = 'true'\nshared = 'echo b'\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("10-a.toml"),
+        format!("{launcher}[menu]\na = 'true'\nshared = 'echo a'\n"),
+    )
+    .unwrap();
+    fs::write(dir.join("notes.txt"), "not a config").unwrap();
+
+    let output = dmm(&["--dump-config", dir.to_str().unwrap()]);
+    let dumped = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{dumped}");
+    assert!(
+        dumped.contains("[menu.a]") && dumped.contains("[menu.b]"),
+        "{dumped}"
+    );
+    assert!(dumped.contains("run = 'echo a'"), "{dumped}");
+    assert!(!dumped.contains("echo b"), "{dumped}");
+
+    let empty = pattern.with_file_name("empty.d");
+    fs::create_dir_all(&empty).unwrap();
+    let output = dmm(&["--dump-config", empty.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("has no `.toml` files"), "{stderr}");
+}
+
+/// Serve each `(path, body)` of `files` over http on localhost, returning the server's url.
+#[cfg(feature = "url")]
+fn serve(files: &'static [(&'static str, &'static str)]) -> String {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = String::new();
+            BufReader::new(&stream).read_line(&mut request).unwrap();
+            let path = request.split(' ').nth(1).unwrap_or_default();
+            let response = match files.iter().find(|(file, _)| *file == path) {
+                Some((_, body)) => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                ),
+                None => String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"),
+            };
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    url
+}
+
+#[cfg(feature = "url")]
+#[test]
+fn downloaded_configs_include_relative_to_their_url() {
+    let url = serve(&[
+        (
+            "/menus/main.toml",
+            "include = ['extra.toml']\n[menu]\nmain = 'echo main'\n",
+        ),
+        ("/menus/extra.toml", "[menu]\nextra = 'echo extra'\n"),
+        (
+            "/cwd.toml",
+            "[menu]\nrelative = { run = 'ls', cwd = 'src' }\n",
+        ),
+        ("/local.toml", "include = ['file:///etc/hostname']\n"),
+    ]);
+
+    let output = dmm(&["--dump-config", &format!("{url}/menus/main.toml")]);
+    let dumped = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(
+        dumped.contains("[menu.main]") && dumped.contains("[menu.extra]"),
+        "{dumped}"
+    );
+
+    // A downloaded config has no directory, and never reads local files.
+    let output = dmm(&["--dump-config", &format!("{url}/cwd.toml")]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("`menu.relative.cwd` must be an absolute path"),
+        "{stderr}"
+    );
+
+    let output = dmm(&["--dump-config", &format!("{url}/local.toml")]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("can only include http and https urls"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn launcher_list_uses_the_first_found() {
+    let pattern = fake_launcher("launcher_list", "sed -n 1p", "");
+    let launcher = pattern.with_file_name("launcher");
+    fs::write(
+        &pattern,
+        format!(
+            "config.show-tags = false\n\
+             config.launcher = ['dmm-missing-launcher', '{}']\n\
+             [menu]\nonly = 'echo only'\n",
+            launcher.display()
+        ),
+    )
+    .unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "echo only\n");
+
+    fs::write(
+        &pattern,
+        "config.launcher = ['dmm-missing-launcher', '/nonexistent/launcher']\n\
+         [menu]\nonly = 'echo only'\n",
+    )
+    .unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains(
+            "none of the launchers in `config.launcher` were found: \
+             `dmm-missing-launcher`, `/nonexistent/launcher`"
+        ),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn ad_hoc_commands_keep_their_whitespace() {
+    let pattern = fake_launcher(
+        "ad_hoc_whitespace",
+        "cat > /dev/null; printf '  echo  spaced \\t\\n'",
+        "config.custom = true\n[menu]\nonly = 'echo only'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "  echo  spaced \t\n"
+    );
+}
+
+#[test]
+fn sort_orders_entries_in_a_group() {
+    for (test, sort, menu) in [
+        ("sort_alpha", "alpha", "apple\nBanana\ncherry\n"),
+        ("sort_alpha_desc", "alpha-desc", "cherry\nBanana\napple\n"),
+        ("sort_none", "none", "cherry\napple\nBanana\n"),
+    ] {
+        let pattern = fake_launcher(
+            test,
+            "tee \"$(dirname \"$0\")/menu\" > /dev/null",
+            &format!(
+                "config.show-tags = false\nconfig.sort = '{sort}'\n\
+                 config.source = {{ source = ['printf', 'cherry\\napple\\nBanana\\n'], run = 'true' }}\n"
+            ),
+        );
+        let output = dmm(&[pattern.to_str().unwrap()]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(output.status.success(), "{stderr}");
+        let shown = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+        assert_eq!(shown, menu, "{sort}");
+    }
+}
+
+#[test]
+fn notify_reports_commands_that_cant_run() {
+    let pattern = fake_launcher(
+        "notify",
+        "sed -n 1p",
+        "config.show-tags = false\nconfig.notify = true\n\
+         [menu]\nbroken = ['dmm-missing-command']\n",
+    );
+    let dir = pattern.parent().unwrap();
+    let notify_send = dir.join("notify-send");
+    fs::write(
+        &notify_send,
+        "#!/bin/sh\nprintf '%s\\n' \"$@\" > \"$(dirname \"$0\")/notification\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&notify_send, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!("{}:{}", dir.display(), env::var("PATH").unwrap());
+    let output = dmm_command(&[pattern.to_str().unwrap()])
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("can't run `broken`"), "{stderr}");
+
+    let notification = fs::read_to_string(dir.join("notification")).unwrap();
+    let (summary, body) = notification
+        .strip_prefix("--app-name=dmm\n--urgency=critical\n--\n")
+        .and_then(|args| args.split_once('\n'))
+        .unwrap();
+    assert_eq!(summary, "dmm: can't run broken");
+    assert!(body.starts_with("can't run `broken`\n"), "{body}");
+    assert!(
+        body.contains("`dmm-missing-command` (is it installed?)"),
+        "{body}"
+    );
+}
+
+#[test]
+fn fd_reads_the_config_from_a_file_descriptor() {
+    let pattern = fake_launcher(
+        "fd",
+        "sed -n 1p",
+        "config.show-tags = false\n[menu]\nonly = 'echo only'\n",
+    );
+    // The test harness can't open a file descriptor for the child, so a shell does it.
+    let home = env::temp_dir().join(format!("dmm-test-{}-home", process::id()));
+    let output = Command::new("sh")
+        .args(["-c", "exec \"$0\" --print --fd 3 3< \"$1\""])
+        .arg(env!("CARGO_BIN_EXE_dmm"))
+        .arg(&pattern)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "echo only\n");
+
+    let output = dmm(&["--print", "--fd", "97"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("unable to read config from file descriptor `97`"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn stdin_entries_are_shown_each_running_the_same_command() {
+    let pattern = fake_launcher(
+        "stdin_entries",
+        "tee \"$(dirname \"$0\")/menu\" | sed -n 2p",
+        "config.show-tags = false\n",
+    );
+    // `--stdin-entries` can't be given a pattern, so the launcher is set in the home config.
+    let home = pattern.with_file_name("home");
+    fs::create_dir_all(home.join("dmm")).unwrap();
+    fs::rename(&pattern, home.join("dmm/config.toml")).unwrap();
+
+    let run = |input: &str| {
+        let mut child = dmm_command(&["--print", "--stdin-entries", "--run", "echo {name}"])
+            .env("XDG_CONFIG_HOME", &home)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let output = run("beta\nalpha\n\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "echo beta\n");
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "alpha\nbeta\n");
+
+    let output = run("");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "");
+}
+
+#[test]
+fn interrupting_dmm_kills_the_launcher() {
+    let pattern = fake_launcher(
+        "interrupt",
+        "echo $$ > \"$(dirname \"$0\")/pid\"; exec sleep 30",
+        "[menu]\nonly = 'echo only'\n",
+    );
+    let pid_path = pattern.with_file_name("pid");
+    let _ = fs::remove_file(&pid_path);
+    let mut dmm = dmm_command(&[pattern.to_str().unwrap()])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let pid = loop {
+        match fs::read_to_string(&pid_path) {
+            Ok(pid) if pid.ends_with('\n') => break pid.trim().to_owned(),
+            _ => thread::sleep(Duration::from_millis(10)),
+        }
+    };
+    let kill = |args: &[&str]| {
+        Command::new("kill")
+            .args(args)
+            .stderr(Stdio::null())
+            .status()
+            .unwrap()
+    };
+    assert!(kill(&["-TERM", &dmm.id().to_string()]).success());
+
+    let status = dmm.wait().unwrap();
+    assert_eq!(status.code(), Some(143));
+    // Once it's killed and reaped, there's no process to signal.
+    assert!(!kill(&["-0", &pid]).success());
+}
+
+#[test]
+fn select_starts_the_search_or_moves_matches_to_the_top() {
+    let pattern = fake_launcher(
+        "select",
+        "tee \"$(dirname \"$0\")/menu\" > /dev/null; echo \"$@\" > \"$(dirname \"$0\")/args\"",
+        "config.show-tags = false\nconfig.select = 'FOX'\n\
+         [menu]\nbash = 'echo bash'\nfirefox = 'echo firefox'\nmpv = 'echo mpv'\nfoxit = 'echo foxit'\n",
+    );
+    let output = dmm(&[pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "firefox\nfoxit\nbash\nmpv\n");
+
+    // rofi can start with the search itself, so the menu is left in order.
+    let rofi = pattern.with_file_name("rofi");
+    fs::copy(pattern.with_file_name("launcher"), &rofi).unwrap();
+    let config = fs::read_to_string(&pattern).unwrap();
+    fs::write(&pattern, config.replace("/launcher'", "/rofi'")).unwrap();
+    let output = dmm(&[pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu, "bash\nfirefox\nfoxit\nmpv\n");
+    let args = fs::read_to_string(pattern.with_file_name("args")).unwrap();
+    assert!(args.trim_end().ends_with("-filter FOX"), "{args}");
+}
+
+#[test]
+fn launcher_args_are_chosen_by_the_launchers_name() {
+    let pattern = fake_launcher(
+        "launcher_args",
+        "echo \"$@\" > \"$(dirname \"$0\")/args\"; sed -n 1p",
+        "config.launcher-args = { rofi = \"-theme 'my theme'\", default = ['-l', '3'] }\n\
+         config.dmenu.font = 'monospace'\n\
+         [menu]\nhtop = 'htop'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    let args = fs::read_to_string(pattern.with_file_name("args")).unwrap();
+    assert!(args.contains("-l 3"), "{args}");
+    assert!(!args.contains("-theme"), "{args}");
+
+    let rofi = pattern.with_file_name("rofi");
+    fs::copy(pattern.with_file_name("launcher"), &rofi).unwrap();
+    let config = fs::read_to_string(&pattern).unwrap();
+    fs::write(&pattern, config.replace("/launcher'", "/rofi'")).unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    let args = fs::read_to_string(pattern.with_file_name("args")).unwrap();
+    assert!(args.contains("-theme my theme"), "{args}");
+    assert!(!args.contains("-l 3"), "{args}");
+
+    // Neither gets dmenu's flags from `config.dmenu`, but dmenu does.
+    for (name, dmenu) in [("fuzzel", false), ("dmenu", true)] {
+        let launcher = pattern.with_file_name(name);
+        fs::copy(pattern.with_file_name("launcher"), &launcher).unwrap();
+        fs::write(&pattern, config.replace("/launcher'", &format!("/{name}'"))).unwrap();
+        let output = dmm(&["--print", pattern.to_str().unwrap()]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{stderr}");
+        let args = fs::read_to_string(pattern.with_file_name("args")).unwrap();
+        let words = args.split_whitespace().collect::<Vec<&str>>();
+        assert_eq!(words.contains(&"-i"), dmenu, "{name}: {args}");
+        assert_eq!(words.contains(&"-fn"), dmenu, "{name}: {args}");
+        assert_eq!(words.first() == Some(&"--dmenu"), !dmenu, "{name}: {args}");
+        assert!(args.contains("-l 3"), "{name}: {args}");
+    }
+}
+
+#[test]
+fn page_size_splits_the_menu_into_pages() {
+    let pattern = fake_launcher(
+        "page_size",
+        "dir=\"$(dirname \"$0\")\"\n\
+         n=$(($(cat \"$dir/count\" 2> /dev/null || echo 0) + 1))\n\
+         echo $n > \"$dir/count\"\n\
+         case $n in\n\
+         1) tee \"$dir/menu$n\" | grep Next ;;\n\
+         2) tee \"$dir/menu$n\" | grep Prev ;;\n\
+         *) tee \"$dir/menu$n\" | sed -n 2p ;;\n\
+         esac",
+        "config.page-size = 2\n\
+         [menu]\na = 'echo a'\nb = 'echo b'\nc = 'echo c'\nd = 'echo d'\ne = 'echo e'\n",
+    );
+    let _ = fs::remove_file(pattern.with_file_name("count"));
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo b\n",
+        "{stderr}"
+    );
+
+    let menu = |n: usize| {
+        fs::read_to_string(pattern.with_file_name(format!("menu{n}")))
+            .unwrap()
+            .lines()
+            .map(|line| {
+                line.trim_end_matches(['\u{200b}', '\u{200c}', '\u{200d}'])
+                    .to_owned()
+            })
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(menu(1), ["a", "b", "Next →"]);
+    assert_eq!(menu(2), ["← Prev", "c", "d", "Next →"]);
+    assert_eq!(menu(3), ["a", "b", "Next →"]);
+}
+
+#[test]
+fn group_by_category_shows_categories_as_submenus() {
+    let pattern = fake_launcher(
+        "group_by_category",
+        "dir=\"$(dirname \"$0\")\"\n\
+         n=$(($(cat \"$dir/count\" 2> /dev/null || echo 0) + 1))\n\
+         echo $n > \"$dir/count\"\n\
+         case $n in\n\
+         1) tee \"$dir/menu$n\" | grep Media ;;\n\
+         *) tee \"$dir/menu$n\" | sed -n 2p ;;\n\
+         esac",
+        "config.group-by-category = true\n\
+         [menu]\n\
+         htop = 'echo htop'\n\
+         music = { run = 'echo music', category = 'Media' }\n\
+         mail = { run = 'echo mail', category = 'Web' }\n\
+         video = { run = 'echo video', category = 'Media' }\n",
+    );
+    let _ = fs::remove_file(pattern.with_file_name("count"));
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo video\n",
+        "{stderr}"
+    );
+
+    let menu = |n: usize| {
+        fs::read_to_string(pattern.with_file_name(format!("menu{n}")))
+            .unwrap()
+            .lines()
+            .map(|line| {
+                line.trim_end_matches(['\u{200b}', '\u{200c}', '\u{200d}'])
+                    .to_owned()
+            })
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(menu(1), ["Web", "Media", "Other"]);
+    assert_eq!(menu(2), ["music", "video"]);
+}
+
+#[test]
+fn markup_is_shown_by_launchers_that_support_it_and_stripped_otherwise() {
+    let pattern = fake_launcher(
+        "markup",
+        "echo \"$@\" > \"$(dirname \"$0\")/args\"; tee \"$(dirname \"$0\")/menu\" | sed -n 1p",
+        "config.markup = true\nconfig.numbered = true\n[menu]\n'<b>fire</b>fox &amp; more' = 'echo firefox'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo firefox\n",
+        "{stderr}"
+    );
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(menu.replace('\u{200c}', ""), "0: firefox & more\n");
+    let args = fs::read_to_string(pattern.with_file_name("args")).unwrap();
+    assert!(!args.contains("-markup-rows"), "{args}");
+
+    let rofi = pattern.with_file_name("rofi");
+    fs::copy(pattern.with_file_name("launcher"), &rofi).unwrap();
+    let config = fs::read_to_string(&pattern).unwrap();
+    fs::write(&pattern, config.replace("/launcher'", "/rofi'")).unwrap();
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo firefox\n",
+        "{stderr}"
+    );
+    let menu = fs::read_to_string(pattern.with_file_name("menu")).unwrap();
+    assert_eq!(
+        menu.replace('\u{200c}', ""),
+        "0: <b>fire</b>fox &amp; more\n"
+    );
+    let args = fs::read_to_string(pattern.with_file_name("args")).unwrap();
+    assert!(args.contains("-markup-rows"), "{args}");
+}
+
+#[test]
+fn check_commands_skip_entries_that_succeed() {
+    let pattern = fake_launcher(
+        "check",
+        "cat > /dev/null; printf 'running\\nstopped\\n'",
+        "config.show-tags = false\nconfig.shell = ['sh', '-c']\nconfig.wait = true\n[menu]\n\
+         running = { run = 'touch ran-running', check = 'exit 0' }\n\
+         stopped = { run = 'touch ran-stopped', check = ['false'] }\n",
+    );
+    let dir = pattern.parent().unwrap();
+    let output = dmm_command(&[pattern.to_str().unwrap()])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+    assert!(!dir.join("ran-running").exists());
+    assert!(dir.join("ran-stopped").exists());
+}
+
+#[test]
+fn tag_placeholder_is_the_tag_shown_in_the_menu() {
+    let pattern = fake_launcher(
+        "tag_placeholder",
+        "sed -n 2p",
+        "config.numbered = true\nconfig.tags = 'alpha'\n\
+         [menu]\nfirst = 'echo {tag}'\nsecond = 'echo {tag}'\n",
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo b\n",
+        "{stderr}"
+    );
+
+    let entries = (0..11)
+        .map(|i| format!("entry-{i:02} = 'echo {{tag}}'\n"))
+        .collect::<String>();
+    let pattern = fake_launcher(
+        "tag_placeholder_padded",
+        "sed -n 8p",
+        &format!("config.numbered = true\nconfig.tag-padding = true\n[menu]\n{entries}"),
+    );
+    let output = dmm(&["--print", pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "echo 07\n",
+        "{stderr}"
+    );
+}
+
+#[test]
+fn commands_know_which_entry_was_selected() {
+    let pattern = fake_launcher(
+        "selected_env",
+        "sed -n 2p",
+        r#"config.shell = ['sh', '-c']
+config.wait = true
+config.numbered = true
+config.tags = 'alpha'
+[menu]
+first = 'true'
+third = 'true'
+second = '''printf '%s|%s|%s|%s' "$DMENU_NAME" "$DMENU_INDEX" "$DMENU_TAG" "$DMENU_COUNT" > env'''
+"#,
+    );
+    let dir = pattern.parent().unwrap();
+    let output = dmm_command(&[pattern.to_str().unwrap()])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(fs::read_to_string(dir.join("env")).unwrap(), "second|1|b|3");
+
+    // Ad-hoc commands weren't in the menu, so they have no index or tag.
+    let pattern = fake_launcher(
+        "selected_env_ad_hoc",
+        r#"cat > /dev/null; echo 'echo "${DMENU_INDEX-unset} ${DMENU_TAG-unset} $DMENU_COUNT"'"#,
+        "config.shell = ['sh', '-c']\nconfig.wait = true\nconfig.custom = true\n\
+         [menu]\nonly = 'true'\n",
+    );
+    let output = dmm(&[pattern.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "unset unset 1\n");
+}
+
+#[test]
+fn output_json_describes_each_selection() {
+    let pattern = fake_launcher(
+        "output_json",
+        "cat > /dev/null; printf '\\342\\200\\2142\\342\\200\\214: say \"hi\"\\ntouch ad-hoc\\n'",
+        "config.numbered = true\nconfig.custom = true\n[menu]\n\
+         a = 'touch a'\nb = 'touch b'\n'say \"hi\"' = ['echo', 'hi']\n",
+    );
+    let dir = pattern.parent().unwrap();
+    let output = dmm_command(&["--output", "json", pattern.to_str().unwrap()])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"tag\": \"2\", \"index\": 2, \"name\": \"say \\\"hi\\\"\", \"command\": \"echo hi\"}\n\
+         {\"tag\": null, \"index\": null, \"name\": \"touch ad-hoc\", \"command\": \"touch ad-hoc\"}\n"
+    );
+    assert!(!dir.join("ad-hoc").exists());
+}